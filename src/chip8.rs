@@ -1,29 +1,216 @@
+use std::ops::Range;
+
 use rand::random;
 
-const REGISTER_COUNT: usize = 16;
-const MEMORY_SIZE: usize = 4096;
-const MAX_STACK_SIZE: usize = 12;
-const KEYPAD_SIZE: usize = 16;
+use crate::display::{Bitmap, Display};
+use crate::error::{Chip8Error, LoadError, StateError};
+use crate::font::{BIG_FONT, BIG_FONT_CHARACTER_SIZE, BIG_FONT_SIZE, FONT_CHARACTER_SIZE, FONT_SIZE, FontSet};
+use crate::megachip::{self, BlendMode, DigitizedSound};
+use crate::quirks::Quirks;
+use crate::random::{RandomAlgorithm, RandomSource};
+use crate::state::{SparseBytes, StateReader, StateWriter};
+use crate::variant::Variant;
+use crate::watch::{Access, WatchHit, Watchpoint};
+
+/// The number of general purpose registers, V0 to VF
+pub const REGISTER_COUNT: usize = 16;
+/// The size of memory in bytes for the variants without extended memory
+pub const MEMORY_SIZE: usize = 0x1000;
+/// The size of memory in bytes for XO-CHIP, the full range of a 16 bit address
+pub const XO_CHIP_MEMORY_SIZE: usize = 0x10000;
+/// The size of memory in bytes for MEGA-CHIP, the full range of a 24 bit address
+pub const MEGA_CHIP_MEMORY_SIZE: usize = 0x1000000;
+/// The deepest stack allowed by any variant
+const MAX_STACK_SIZE: usize = 16;
+/// The most RPL user flags saved and loaded by FX75 and FX85 in any variant
+const FLAG_COUNT: usize = 16;
+/// The number of bitplanes in the screen
+pub const PLANE_COUNT: usize = 2;
+/// The number of bytes in the audio pattern loaded by F002
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+/// The pitch that plays the audio pattern at 4000 bits per second
+const DEFAULT_PITCH: u8 = 64;
+/// The number of keys on the hexadecimal keypad
+pub const KEYPAD_SIZE: usize = 16;
 const INSTRUCTION_SIZE: usize = 2;
+/// The frequency the delay and sound timers count down at in hertz
+pub const TIMER_FREQUENCY: u32 = 60;
+/// The logical width of the screen in pixels
+pub const SCREEN_WIDTH: usize = 64;
 /// The logical height of the screen in pixels
-const SCREEN_HEIGHT: usize = 32;
+pub const SCREEN_HEIGHT: usize = 32;
+/// The logical height of the screen in pixels in the two page mode of hi-res CHIP-8
+pub const TWO_PAGE_SCREEN_HEIGHT: usize = 64;
+/// The logical width of the screen in pixels in high resolution mode
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+/// The logical height of the screen in pixels in high resolution mode
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+/// The logical width of the screen in pixels in MEGA-CHIP mode
+pub const MEGA_CHIP_SCREEN_WIDTH: usize = 256;
+/// The logical height of the screen in pixels in MEGA-CHIP mode
+pub const MEGA_CHIP_SCREEN_HEIGHT: usize = 192;
+/// The number of colors in the MEGA-CHIP palette, where color 0 is transparent
+const PALETTE_SIZE: usize = 256;
+/// The largest width and height of MEGA-CHIP sprites, which is also their size until set by 03NN and 04NN
+const MAX_SPRITE_SIZE: usize = 256;
+/// The palette index recorded for pixels of 1-bit sprites drawn in MEGA-CHIP mode, such as font characters
+const MONOCHROME_COLOR_INDEX: u8 = 0xFF;
+/// The ARGB color of 1-bit sprites drawn in MEGA-CHIP mode
+const MONOCHROME_COLOR: u32 = 0xFFFF_FFFF;
+/// The size of the header before the samples of a MEGA-CHIP digitized sound
+const DIGITIZED_SOUND_HEADER_SIZE: usize = 6;
+/// The address programs are loaded at and started from by default
+pub const PROGRAM_START_ADDRESS: usize = 0x200;
+/// The address hi-res CHIP-8 programs continue from after the patched interpreter at `PROGRAM_START_ADDRESS`
+const TWO_PAGE_PROGRAM_ADDRESS: usize = 0x2C0;
+/// The address programs for CHIP-8X are loaded at and started from, after its larger interpreter
+pub const CHIP_8X_START_ADDRESS: usize = 0x300;
+/// The address programs for the ETI-660 are loaded at and started from
+pub const ETI_660_START_ADDRESS: usize = 0x600;
+/// The number of columns in the CHIP-8X color overlay, each coloring 8 pixels of a row
+pub const COLOR_ZONE_COLUMNS: usize = 8;
+/// The width of a color zone in pixels
+const COLOR_ZONE_WIDTH: usize = SCREEN_WIDTH / COLOR_ZONE_COLUMNS;
+/// The number of rows of color zones colored at once by BXY0
+const COLOR_BLOCK_HEIGHT: usize = 4;
+/// The number of background colors cycled through by 02A0
+const BACKGROUND_COLOR_COUNT: u8 = 4;
+/// The foreground color every zone starts with, which is red
+const DEFAULT_ZONE_COLOR: u8 = 0x1;
+/// The address of the first font character in memory
+/// The font occupies `0x050..0x0A0`, inside the area reserved for the interpreter
+const FONT_ADDRESS: usize = 0x050;
+/// The address of the first large font character in memory
+/// The large font occupies `0x0A0..0x140`, directly after the small font
+const BIG_FONT_ADDRESS: usize = 0x0A0;
+
+/// Options for creating a processor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Config {
+    pub variant: Variant,
+    pub quirks: Quirks,
+    pub font: FontSet,
+    /// The algorithm CXNN draws random numbers from
+    pub random: RandomAlgorithm,
+    /// The seed of the random numbers, or `None` to seed them from the operating system
+    pub seed: Option<u64>,
+}
 
 pub struct Chip8 {
     registers: [u8; REGISTER_COUNT],
     index_register: usize,
     program_counter: usize,
     stack_pointer: usize,
-    memory: [u8; MEMORY_SIZE],
+    /// Sized for the variant, either `MEMORY_SIZE` or `XO_CHIP_MEMORY_SIZE` bytes
+    memory: Vec<u8>,
     stack: [usize; MAX_STACK_SIZE],
     sound_timer: u8,
     delay_timer: u8,
     keypad: [bool; KEYPAD_SIZE],
-    screen: [u64; SCREEN_HEIGHT],
-    draw_flag: bool
+    /// The second keypad of CHIP-8X
+    second_keypad: [bool; KEYPAD_SIZE],
+    /// The key pressed during FX0A that must be released before execution resumes
+    awaited_key: Option<usize>,
+    /// The keys pressed since the last cycle, so that FX0A sees a key pressed and released between two cycles
+    tapped_keys: [bool; KEYPAD_SIZE],
+    /// The bitplanes of the screen, sized for the current resolution
+    display: Box<dyn Display>,
+    /// The bitplanes drawn to, scrolled and cleared, with plane `n` in bit `n`
+    selected_planes: u8,
+    /// Set by the hi-res CHIP-8 start sequence to show two pages of 64x32 pixels as a 64x64 screen
+    two_page: bool,
+    draw_flag: bool,
+    /// The RPL user flags of the HP-48
+    flags: [u8; FLAG_COUNT],
+    /// The 1-bit samples loaded by F002, or `None` if the program has not loaded any
+    audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+    /// Sets the playback rate of `audio_pattern`
+    pitch: u8,
+    /// The foreground color of each 8x1 pixel zone of the low resolution screen for CHIP-8X
+    color_zones: [[u8; COLOR_ZONE_COLUMNS]; SCREEN_HEIGHT],
+    /// The background color of CHIP-8X, cycled by 02A0
+    background_color: u8,
+    /// The value last written to the output port by FXF8 that has not been taken by the frontend
+    output_port: Option<u8>,
+    /// The value waiting on the input port to be read by FXFB
+    input_port: Option<u8>,
+    /// Set by 0011 to draw to the 256x192 MEGA-CHIP screen
+    megachip_mode: bool,
+    /// The palette index last drawn to each pixel of the MEGA-CHIP screen, used for collisions
+    /// Empty unless the variant is MEGA-CHIP
+    megachip_indices: Vec<u8>,
+    /// The ARGB color of each pixel of the MEGA-CHIP screen, in rows from the top left
+    /// Empty unless the variant is MEGA-CHIP
+    megachip_pixels: Vec<u32>,
+    /// The ARGB colors loaded by 02NN
+    palette: [u32; PALETTE_SIZE],
+    /// The address of the font character FX29 or FX30 last pointed `index_register` at,
+    /// which MEGA-CHIP mode draws as a 1-bit sprite while `index_register` still points there
+    font_character: Option<usize>,
+    /// The size of MEGA-CHIP sprites set by 03NN and 04NN
+    sprite_width: usize,
+    sprite_height: usize,
+    /// The opacity of the whole MEGA-CHIP screen set by 05NN
+    screen_alpha: u8,
+    blend_mode: BlendMode,
+    /// Sprites drawn over pixels of this palette index set `registers[0xF]` in MEGA-CHIP mode
+    collision_color: u8,
+    digitized_sound: Option<DigitizedSound>,
+    /// Set when `digitized_sound` is started or stopped
+    sound_changed: bool,
+    variant: Variant,
+    quirks: Quirks,
+    /// The source of the random numbers used by CXNN
+    random: Box<dyn RandomSource>,
+    /// The algorithm behind `random`, or `None` once `set_random_source` has replaced it
+    random_algorithm: Option<RandomAlgorithm>,
+    /// Set by DXYN when `quirks.display_wait` is enabled to end the current frame
+    awaiting_vblank: bool,
+    watchpoints: Vec<Watchpoint>,
+    /// The accesses caught by `watchpoints` that have not been taken by `take_watch_hits`
+    watch_hits: Vec<WatchHit>,
+    /// Set when an instruction accesses memory watched by a stopping watchpoint to end the cycle
+    watch_stopped: bool,
 }
 
 type Opcode = u16;
 
+/// The result of emulating a single cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CycleOutcome {
+    /// The instruction was executed and the program counter advanced
+    Executed,
+    /// The processor is blocked until a key is pressed
+    WaitingForKey,
+    /// The processor is blocked until a value is placed on the CHIP-8X input port
+    WaitingForInput,
+    /// The program jumped to its own address or exited and will never progress
+    Halted,
+    /// The instruction was executed and accessed memory watched by a watchpoint that stops execution
+    Watchpoint,
+}
+
+/// A fault raised by an instruction, before it is tagged with the program counter and opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    StackOverflow,
+    StackUnderflow,
+    UnknownOpcode,
+    MemoryOutOfBounds(usize),
+}
+
+impl Fault {
+    /// Converts the fault into an error raised by `opcode` at address `pc`
+    fn at(self, pc: usize, opcode: Opcode) -> Chip8Error {
+        match self {
+            Fault::StackOverflow => Chip8Error::StackOverflow { pc, opcode },
+            Fault::StackUnderflow => Chip8Error::StackUnderflow { pc, opcode },
+            Fault::UnknownOpcode => Chip8Error::UnknownOpcode { pc, opcode },
+            Fault::MemoryOutOfBounds(address) => Chip8Error::MemoryOutOfBounds { pc, opcode, address },
+        }
+    }
+}
+
 /// Allows the type to be parsed as a CHIP8 opcode
 trait Chip8Opcode {
     /// Splits the opcode into hexadecimal digits
@@ -71,672 +258,4319 @@ impl Chip8Opcode for Opcode {
     }
 }
 
+/// An instruction decoded from an opcode with its operands, named after the `op_` method that emulates it
+/// What each instruction does is explained once, by `Instruction::explain`, rather than on its `op_` method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    Op0010,
+    Op0011,
+    Op01nn(u8),
+    Op02nn(u8),
+    Op03nn(u8),
+    Op04nn(u8),
+    Op05nn(u8),
+    Op060n(u8),
+    Op07nn,
+    Op080n(u8),
+    Op09nn(u8),
+    Op0230,
+    Op02a0,
+    Op00bn(u8),
+    Op00cn(u8),
+    Op00dn(u8),
+    Op00e0,
+    Op00ee,
+    Op00fb,
+    Op00fc,
+    Op00fd,
+    Op00fe,
+    Op00ff,
+    Op1260,
+    Op1nnn(usize),
+    Op2nnn(usize),
+    Op3xnn(usize, u8),
+    Op4xnn(usize, u8),
+    Op5xy0(usize, usize),
+    Op5xy1(usize, usize),
+    Op5xy2(usize, usize),
+    Op5xy3(usize, usize),
+    Op6xnn(usize, u8),
+    Op7xnn(usize, u8),
+    Op8xy0(usize, usize),
+    Op8xy1(usize, usize),
+    Op8xy2(usize, usize),
+    Op8xy3(usize, usize),
+    Op8xy4(usize, usize),
+    Op8xy5(usize, usize),
+    Op8xy6(usize, usize),
+    Op8xy7(usize, usize),
+    Op8xye(usize, usize),
+    Op9xy0(usize, usize),
+    OpAnnn(usize),
+    OpBxy0(usize, usize),
+    OpBxyn(usize, usize, u8),
+    OpBnnn(usize),
+    OpCxnn(usize, u8),
+    OpDxyn(usize, usize, u8),
+    OpEx9e(usize),
+    OpExa1(usize),
+    OpExf2(usize),
+    OpExf5(usize),
+    OpF000,
+    OpFn01(u8),
+    OpF002,
+    OpFx07(usize),
+    OpFx0a(usize),
+    OpFx15(usize),
+    OpFx18(usize),
+    OpFx1e(usize),
+    OpFx29(usize),
+    OpFx30(usize),
+    OpFx33(usize),
+    OpFx3a(usize),
+    OpFx55(usize),
+    OpFx65(usize),
+    OpFx75(usize),
+    OpFx85(usize),
+    OpFxf8(usize),
+    OpFxfb(usize),
+}
+
+impl Instruction {
+    /// Gets the opcode pattern of the instruction, including the second word of the 32 bit ones
+    fn pattern(&self) -> &'static str {
+        match self {
+            Instruction::Op0010 => "0010",
+            Instruction::Op0011 => "0011",
+            Instruction::Op01nn(_) => "01NN NNNN",
+            Instruction::Op02nn(_) => "02NN",
+            Instruction::Op03nn(_) => "03NN",
+            Instruction::Op04nn(_) => "04NN",
+            Instruction::Op05nn(_) => "05NN",
+            Instruction::Op060n(_) => "060N",
+            Instruction::Op07nn => "07NN",
+            Instruction::Op080n(_) => "080N",
+            Instruction::Op09nn(_) => "09NN",
+            Instruction::Op0230 => "0230",
+            Instruction::Op02a0 => "02A0",
+            Instruction::Op00bn(_) => "00BN",
+            Instruction::Op00cn(_) => "00CN",
+            Instruction::Op00dn(_) => "00DN",
+            Instruction::Op00e0 => "00E0",
+            Instruction::Op00ee => "00EE",
+            Instruction::Op00fb => "00FB",
+            Instruction::Op00fc => "00FC",
+            Instruction::Op00fd => "00FD",
+            Instruction::Op00fe => "00FE",
+            Instruction::Op00ff => "00FF",
+            Instruction::Op1260 => "1260",
+            Instruction::Op1nnn(_) => "1NNN",
+            Instruction::Op2nnn(_) => "2NNN",
+            Instruction::Op3xnn(..) => "3XNN",
+            Instruction::Op4xnn(..) => "4XNN",
+            Instruction::Op5xy0(..) => "5XY0",
+            Instruction::Op5xy1(..) => "5XY1",
+            Instruction::Op5xy2(..) => "5XY2",
+            Instruction::Op5xy3(..) => "5XY3",
+            Instruction::Op6xnn(..) => "6XNN",
+            Instruction::Op7xnn(..) => "7XNN",
+            Instruction::Op8xy0(..) => "8XY0",
+            Instruction::Op8xy1(..) => "8XY1",
+            Instruction::Op8xy2(..) => "8XY2",
+            Instruction::Op8xy3(..) => "8XY3",
+            Instruction::Op8xy4(..) => "8XY4",
+            Instruction::Op8xy5(..) => "8XY5",
+            Instruction::Op8xy6(..) => "8XY6",
+            Instruction::Op8xy7(..) => "8XY7",
+            Instruction::Op8xye(..) => "8XYE",
+            Instruction::Op9xy0(..) => "9XY0",
+            Instruction::OpAnnn(_) => "ANNN",
+            Instruction::OpBxy0(..) => "BXY0",
+            Instruction::OpBxyn(..) => "BXYN",
+            Instruction::OpBnnn(_) => "BNNN",
+            Instruction::OpCxnn(..) => "CXNN",
+            Instruction::OpDxyn(..) => "DXYN",
+            Instruction::OpEx9e(_) => "EX9E",
+            Instruction::OpExa1(_) => "EXA1",
+            Instruction::OpExf2(_) => "EXF2",
+            Instruction::OpExf5(_) => "EXF5",
+            Instruction::OpF000 => "F000 NNNN",
+            Instruction::OpFn01(_) => "FN01",
+            Instruction::OpF002 => "F002",
+            Instruction::OpFx07(_) => "FX07",
+            Instruction::OpFx0a(_) => "FX0A",
+            Instruction::OpFx15(_) => "FX15",
+            Instruction::OpFx18(_) => "FX18",
+            Instruction::OpFx1e(_) => "FX1E",
+            Instruction::OpFx29(_) => "FX29",
+            Instruction::OpFx30(_) => "FX30",
+            Instruction::OpFx33(_) => "FX33",
+            Instruction::OpFx3a(_) => "FX3A",
+            Instruction::OpFx55(_) => "FX55",
+            Instruction::OpFx65(_) => "FX65",
+            Instruction::OpFx75(_) => "FX75",
+            Instruction::OpFx85(_) => "FX85",
+            Instruction::OpFxf8(_) => "FXF8",
+            Instruction::OpFxfb(_) => "FXFB",
+        }
+    }
+
+    /// Explains what the instruction does under `quirks`, in MEGA-CHIP mode if `megachip_mode` is set
+    fn explain(&self, quirks: &Quirks, megachip_mode: bool) -> &'static str {
+        match self {
+            Instruction::Op0010 => "Leaves MEGA-CHIP mode and clears the screen",
+            Instruction::Op0011 => "Enters MEGA-CHIP mode and clears the screen",
+            Instruction::Op01nn(_) => {
+                "Sets index_register to the 24 bit address with nn as the highest byte and the two bytes following the instruction as the rest"
+            }
+            Instruction::Op02nn(_) => {
+                "Loads nn ARGB colors from memory starting at index_register into the palette, starting from color 1"
+            }
+            Instruction::Op03nn(_) => "Sets the width of MEGA-CHIP sprites to nn pixels, where 0 means 256",
+            Instruction::Op04nn(_) => "Sets the height of MEGA-CHIP sprites to nn pixels, where 0 means 256",
+            Instruction::Op05nn(_) => "Sets the opacity of the MEGA-CHIP screen to nn",
+            Instruction::Op060n(_) => "Plays the digitized sound at index_register, looping it if n is 0",
+            Instruction::Op07nn => "Stops the digitized sound",
+            Instruction::Op080n(_) => {
+                "Sets how MEGA-CHIP sprites are blended with the screen to normal, 25%, 50%, additive or multiply for n from 0 to 4"
+            }
+            Instruction::Op09nn(_) => "Sets the palette index that MEGA-CHIP sprites collide with to nn",
+            Instruction::Op0230 => "Clears the screen in the two page mode of hi-res CHIP-8",
+            Instruction::Op02a0 => "Cycles the CHIP-8X background color through blue, black, green and red",
+            Instruction::Op00bn(_) => {
+                "Scrolls the MEGA-CHIP screen up n pixels, or the selected planes outside MEGA-CHIP mode"
+            }
+            Instruction::Op00cn(_) => {
+                "Scrolls the selected planes down n pixels, or the MEGA-CHIP screen in MEGA-CHIP mode"
+            }
+            Instruction::Op00dn(_) => {
+                "Scrolls the selected planes up n pixels, or the MEGA-CHIP screen in MEGA-CHIP mode"
+            }
+            Instruction::Op00e0 => "Clears the selected planes, or the MEGA-CHIP screen in MEGA-CHIP mode",
+            Instruction::Op00ee => "Returns from the current subroutine",
+            Instruction::Op00fb => {
+                "Scrolls the selected planes right 4 pixels, or the MEGA-CHIP screen in MEGA-CHIP mode"
+            }
+            Instruction::Op00fc => {
+                "Scrolls the selected planes left 4 pixels, or the MEGA-CHIP screen in MEGA-CHIP mode"
+            }
+            Instruction::Op00fd => "Exits the interpreter",
+            Instruction::Op00fe => "Switches to the low resolution mode and clears the screen",
+            Instruction::Op00ff => "Switches to the high resolution mode and clears the screen",
+            Instruction::Op1260 => {
+                    "Starts a hi-res CHIP-8 program by switching to the 64x64 screen and jumping over the patched interpreter"
+                },
+            Instruction::Op1nnn(_) => "Sets the program counter to address nnn",
+            Instruction::Op2nnn(_) => "Calls the subroutine at address nnn",
+            Instruction::Op3xnn(..) => "Skips the following instruction if registers[x] = nn",
+            Instruction::Op4xnn(..) => "Skips the following instruction if registers[x] != nn",
+            Instruction::Op5xy0(..) => "Skips the following instruction if registers[x] = registers[y]",
+            Instruction::Op5xy1(..) => {
+                "Adds registers[y] to registers[x] one nibble at a time, keeping the lowest 3 bits of each nibble"
+            }
+            Instruction::Op5xy2(..) => "Stores registers[x] through registers[y] in memory starting at index_register",
+            Instruction::Op5xy3(..) => "Loads registers[x] through registers[y] from memory starting at index_register",
+            Instruction::Op6xnn(..) => "Stores nn in registers[x]",
+            Instruction::Op7xnn(..) => "Adds nn to registers[x], wrapping on overflow",
+            Instruction::Op8xy0(..) => "Stores registers[y] in registers[x]",
+            Instruction::Op8xy1(..) if quirks.logic_resets_vf => {
+                "ORs registers[y] with registers[x] and stores in registers[x], resetting registers[0xF] to 0x00"
+            }
+            Instruction::Op8xy1(..) => "ORs registers[y] with registers[x] and stores in registers[x]",
+            Instruction::Op8xy2(..) if quirks.logic_resets_vf => {
+                "ANDs registers[y] with registers[x] and stores in registers[x], resetting registers[0xF] to 0x00"
+            }
+            Instruction::Op8xy2(..) => "ANDs registers[y] with registers[x] and stores in registers[x]",
+            Instruction::Op8xy3(..) if quirks.logic_resets_vf => {
+                "XORs registers[y] with registers[x] and stores in registers[x], resetting registers[0xF] to 0x00"
+            }
+            Instruction::Op8xy3(..) => "XORs registers[y] with registers[x] and stores in registers[x]",
+            Instruction::Op8xy4(..) => {
+                "Adds registers[y] to registers[x] and stores in registers[x], setting registers[0xF] to 0x01 on overflow and 0x00 otherwise"
+            }
+            Instruction::Op8xy5(..) => {
+                "Subtracts registers[y] from registers[x] and stores in registers[x], setting registers[0xF] to 0x00 on a borrow and 0x01 otherwise"
+            }
+            Instruction::Op8xy6(..) if quirks.shift_uses_vy => {
+                "Stores registers[y] shifted right one bit in registers[x], setting registers[0xF] to the bit shifted out"
+            }
+            Instruction::Op8xy6(..) => {
+                "Shifts registers[x] right one bit, setting registers[0xF] to the bit shifted out"
+            }
+            Instruction::Op8xy7(..) => {
+                "Subtracts registers[x] from registers[y] and stores in registers[x], setting registers[0xF] to 0x00 on a borrow and 0x01 otherwise"
+            }
+            Instruction::Op8xye(..) if quirks.shift_uses_vy => {
+                "Stores registers[y] shifted left one bit in registers[x], setting registers[0xF] to the bit shifted out"
+            }
+            Instruction::Op8xye(..) => {
+                "Shifts registers[x] left one bit, setting registers[0xF] to the bit shifted out"
+            }
+            Instruction::Op9xy0(..) => "Skips the following instruction if registers[x] != registers[y]",
+            Instruction::OpAnnn(_) => "Stores memory address nnn in index_register",
+            Instruction::OpBxy0(..) => {
+                "Sets the foreground color of a rectangle of 8x4 pixel blocks to the lowest 3 bits of registers[y]"
+            }
+            Instruction::OpBxyn(..) => {
+                "Sets the foreground color of n rows of 8x1 pixel zones to the lowest 3 bits of registers[y]"
+            }
+            Instruction::OpBnnn(_) if quirks.jump_uses_vx => {
+                "Sets the program counter to nnn + registers[x], where x is the highest nibble of nnn"
+            }
+            Instruction::OpBnnn(_) => "Sets the program counter to nnn + registers[0x0]",
+            Instruction::OpCxnn(..) => "Sets registers[x] to a random number with a mask of nn",
+            Instruction::OpDxyn(..) if megachip_mode => {
+                "Draws a sprite of palette indices from memory starting at the address index_register at position registers[x], registers[y]"
+            }
+            Instruction::OpDxyn(..) => {
+                "Draws a sprite from memory starting at the address index_register at position registers[x], registers[y]"
+            }
+            Instruction::OpEx9e(_) => "Skips the following instruction if the key registers[x] is pressed",
+            Instruction::OpExa1(_) => "Skips the following instruction if the key registers[x] is not pressed",
+            Instruction::OpExf2(_) => {
+                "Skips the following instruction if the key registers[x] is pressed on the second keypad"
+            }
+            Instruction::OpExf5(_) => {
+                "Skips the following instruction if the key registers[x] is not pressed on the second keypad"
+            }
+            Instruction::OpF000 => {
+                "Sets index_register to the 16 bit address nnnn in the two bytes following the instruction"
+            }
+            Instruction::OpFn01(_) => {
+                "Selects the bitplanes n that are drawn to, scrolled and cleared, with plane n in bit n"
+            }
+            Instruction::OpF002 => "Loads the 16 byte audio pattern from memory starting at index_register",
+            Instruction::OpFx07(_) => "Stores delay_timer in registers[x]",
+            Instruction::OpFx0a(_) => {
+                "Waits for a key to be pressed and then released and stores the key in registers[x]"
+            }
+            Instruction::OpFx15(_) => "Sets delay_timer to registers[x]",
+            Instruction::OpFx18(_) => "Sets sound_timer to registers[x]",
+            Instruction::OpFx1e(_) => "Adds registers[x] to index_register",
+            Instruction::OpFx29(_) => {
+                "Sets index_register to the address of the font character for the low nibble of registers[x]"
+            }
+            Instruction::OpFx30(_) => {
+                "Sets index_register to the address of the large font character for the low nibble of registers[x]"
+            }
+            Instruction::OpFx33(_) => {
+                "Stores the binary-coded decimal representation of registers[x] in memory starting at index_register"
+            }
+            Instruction::OpFx3a(_) => "Sets the pitch of the audio pattern to registers[x]",
+            Instruction::OpFx55(_) if quirks.load_store_increments_index => {
+                "Stores registers[0x0] through registers[x] in memory starting at index_register, advancing index_register past them"
+            }
+            Instruction::OpFx55(_) => "Stores registers[0x0] through registers[x] in memory starting at index_register",
+            Instruction::OpFx65(_) if quirks.load_store_increments_index => {
+                "Loads registers[0x0] through registers[x] from memory starting at index_register, advancing index_register past them"
+            }
+            Instruction::OpFx65(_) => {
+                "Loads registers[0x0] through registers[x] from memory starting at index_register"
+            }
+            Instruction::OpFx75(_) => "Stores registers[0x0] through registers[x] in the RPL user flags",
+            Instruction::OpFx85(_) => "Loads registers[0x0] through registers[x] from the RPL user flags",
+            Instruction::OpFxf8(_) => "Writes registers[x] to the output port",
+            Instruction::OpFxfb(_) => "Waits for a value on the input port and stores it in registers[x]",
+        }
+    }
+}
+
 impl Chip8 {
-    pub fn new() -> Self {
-        // TODO: load font into memory
+    /// Creates a processor that follows `quirks` for ambiguous instructions
+    pub fn new(quirks: Quirks) -> Self {
+        Self::with_config(Config {
+            quirks,
+            ..Config::default()
+        })
+    }
+
+    /// Creates a processor with `font` loaded at `FONT_ADDRESS` instead of the standard font
+    pub fn with_font(quirks: Quirks, font: FontSet) -> Self {
+        Self::with_config(Config {
+            quirks,
+            font,
+            ..Config::default()
+        })
+    }
+
+    /// Creates a processor for `config.variant` with `config.font` loaded at `FONT_ADDRESS`
+    /// and the large font loaded at `BIG_FONT_ADDRESS`
+    pub fn with_config(config: Config) -> Self {
+        Self::with_display(config, Box::new(Bitmap::new()))
+    }
+
+    /// Creates a processor like `with_config` that draws to `display` instead of a `Bitmap`
+    /// The display is cleared and set to the starting resolution of the variant
+    pub fn with_display(config: Config, display: Box<dyn Display>) -> Self {
+        let mut memory = vec![0; config.variant.memory_size()];
+        memory[FONT_ADDRESS..FONT_ADDRESS + FONT_SIZE].copy_from_slice(config.font.glyphs());
+        memory[BIG_FONT_ADDRESS..BIG_FONT_ADDRESS + BIG_FONT_SIZE].copy_from_slice(&BIG_FONT);
+        Self::with_memory(config, display, memory)
+    }
+
+    /// Creates a processor like `with_display` that uses `memory` as it is, without loading the fonts
+    fn with_memory(config: Config, display: Box<dyn Display>, memory: Vec<u8>) -> Self {
+        let megachip_screen_size = if config.variant.has_megachip_instructions() {
+            MEGA_CHIP_SCREEN_WIDTH * MEGA_CHIP_SCREEN_HEIGHT
+        } else {
+            0
+        };
 
-        Chip8 {
+        let mut chip8 = Chip8 {
             registers: [0; REGISTER_COUNT],
             index_register: 0,
-            program_counter: 0,
+            program_counter: config.variant.start_address(),
             stack_pointer: 0,
-            memory: [0; MEMORY_SIZE],
+            memory,
             stack: [0; MAX_STACK_SIZE],
             sound_timer: 0,
             delay_timer: 0,
             keypad: [false; KEYPAD_SIZE],
-            screen: [0; SCREEN_HEIGHT],
-            draw_flag: false
-        }
+            second_keypad: [false; KEYPAD_SIZE],
+            awaited_key: None,
+            tapped_keys: [false; KEYPAD_SIZE],
+            display,
+            selected_planes: 0b01,
+            two_page: config.variant == Variant::HiresChip8,
+            draw_flag: false,
+            flags: [0; FLAG_COUNT],
+            audio_pattern: None,
+            pitch: DEFAULT_PITCH,
+            color_zones: [[DEFAULT_ZONE_COLOR; COLOR_ZONE_COLUMNS]; SCREEN_HEIGHT],
+            background_color: 0,
+            output_port: None,
+            input_port: None,
+            megachip_mode: false,
+            megachip_indices: vec![0; megachip_screen_size],
+            megachip_pixels: vec![0; megachip_screen_size],
+            palette: [0; PALETTE_SIZE],
+            font_character: None,
+            sprite_width: MAX_SPRITE_SIZE,
+            sprite_height: MAX_SPRITE_SIZE,
+            screen_alpha: 0xFF,
+            blend_mode: BlendMode::Normal,
+            collision_color: 0,
+            digitized_sound: None,
+            sound_changed: false,
+            variant: config.variant,
+            quirks: config.quirks,
+            random: config.random.source(config.seed.unwrap_or_else(random)),
+            random_algorithm: Some(config.random),
+            awaiting_vblank: false,
+            watchpoints: Vec::new(),
+            watch_hits: Vec::new(),
+            watch_stopped: false,
+        };
+        chip8.set_lores();
+        chip8
     }
 
-    /// Loads bytes of data into memory starting from address 0x200
-    pub fn load(&mut self, data: &[u8]) {
-        let start_address = 0x200;
-        let max_length = MEMORY_SIZE - start_address;
-        for (i, &byte) in data.iter().take(max_length).enumerate() {
-            self.memory[start_address + i] = byte;
-        }
+    /// Loads bytes of data into memory starting from the start address of the variant,
+    /// which is 0x200 for every variant except CHIP-8X
+    pub fn load(&mut self, data: &[u8]) -> Result<(), LoadError> {
+        self.load_at(data, self.variant.start_address())
     }
 
-    /// Emulates one tick of the processor
-    pub fn emulate_cycle(&mut self) {
-        let opcode = self.fetch_opcode();
-
-        self.program_counter = match opcode.nibbles() {
-            (0x00, 0x00, 0x0E, 0x00) => self.op_00e0(),
-            (0x00, 0x00, 0x0E, 0x0E) => self.op_00ee(),
-            (0x01, _, _, _) => self.op_1nnn(opcode.nnn()),
-            (0x02, _, _, _) => self.op_2nnn(opcode.nnn()),
-            (0x03, _, _, _) => self.op_3xnn(opcode.x(), opcode.nn()),
-            (0x04, _, _, _) => self.op_4xnn(opcode.x(), opcode.nn()),
-            (0x05, _, _, 0x00) => self.op_5xy0(opcode.x(), opcode.y()),
-            (0x06, _, _, _) => self.op_6xnn(opcode.x(), opcode.nn()),
-            (0x07, _, _, _) => self.op_7xnn(opcode.x(), opcode.nn()),
-            (0x08, _, _, 0x00) => self.op_8xy0(opcode.x(), opcode.y()),
-            (0x08, _, _, 0x01) => self.op_8xy1(opcode.x(), opcode.y()),
-            (0x08, _, _, 0x02) => self.op_8xy2(opcode.x(), opcode.y()),
-            (0x08, _, _, 0x03) => self.op_8xy3(opcode.x(), opcode.y()),
-            (0x08, _, _, 0x04) => self.op_8xy4(opcode.x(), opcode.y()),
-            (0x08, _, _, 0x05) => self.op_8xy5(opcode.x(), opcode.y()),
-            (0x08, _, _, 0x06) => self.op_8xy6(opcode.x(), opcode.y()),
-            (0x08, _, _, 0x07) => self.op_8xy7(opcode.x(), opcode.y()),
-            (0x08, _, _, 0x0E) => self.op_8xye(opcode.x(), opcode.y()),
-            (0x09, _, _, 0x00) => self.op_9xy0(opcode.x(), opcode.y()),
-            (0x0A, _, _, _) => self.op_annn(opcode.nnn()),
-            (0x0B, _, _, _) => self.op_bnnn(opcode.nnn()),
-            (0x0C, _, _, _) => self.op_cxnn(opcode.x(),opcode.nn()),
-            (0x0D, _, _, _) => self.op_dxyn(opcode.x(), opcode.y(), opcode.n()),
-            _ => unimplemented!(),
-        };
+    /// Loads bytes of data into memory starting from address `origin`
+    /// Execution begins at `origin`
+    /// Memory is left untouched if the data does not fit
+    pub fn load_at(&mut self, data: &[u8], origin: usize) -> Result<(), LoadError> {
+        let capacity = self.memory.len().saturating_sub(origin);
+        if data.len() > capacity {
+            return Err(LoadError::TooLarge { size: data.len(), capacity });
+        }
 
-        todo!();
+        self.memory[origin..origin + data.len()].copy_from_slice(data);
+        self.program_counter = origin;
+        Ok(())
     }
 
-    /// Fetches the 16 bit opcode pointed to by the program counter
-    fn fetch_opcode(&self) -> Opcode {
-        ((self.memory[self.program_counter] as u16) << 8)
-            | (self.memory[self.program_counter + 1] as u16)
+    /// Marks `key` as held down
+    /// Keys outside `0x0..=0xF` are ignored
+    pub fn press_key(&mut self, key: u8) {
+        if let Some(pressed) = self.keypad.get_mut(key as usize) {
+            *pressed = true;
+            self.tapped_keys[key as usize] = true;
+        }
     }
 
-    /// Opcode: 00E0
-    /// Clears the screen
-    fn op_00e0(&mut self) -> usize {
-        self.screen = [0; SCREEN_HEIGHT];
-        self.draw_flag = true;
-        self.program_counter + INSTRUCTION_SIZE
+    /// Marks `key` as released
+    /// Keys outside `0x0..=0xF` are ignored
+    pub fn release_key(&mut self, key: u8) {
+        if let Some(pressed) = self.keypad.get_mut(key as usize) {
+            *pressed = false;
+        }
     }
 
-    /// Opcode: 00EE
-    /// Returns from the current subroutine
-    fn op_00ee(&mut self) -> usize {
-        self.stack_pointer -= 1;
-        self.stack[self.stack_pointer] + INSTRUCTION_SIZE
+    /// Marks `key` on the second keypad of CHIP-8X as held down
+    /// Keys outside `0x0..=0xF` are ignored
+    pub fn press_second_key(&mut self, key: u8) {
+        if let Some(pressed) = self.second_keypad.get_mut(key as usize) {
+            *pressed = true;
+        }
     }
 
-    /// Opcode: 1NNN
-    /// Sets the program counter to address `nnn`
-    fn op_1nnn(&self, nnn: usize) -> usize {
-        nnn
+    /// Marks `key` on the second keypad of CHIP-8X as released
+    /// Keys outside `0x0..=0xF` are ignored
+    pub fn release_second_key(&mut self, key: u8) {
+        if let Some(pressed) = self.second_keypad.get_mut(key as usize) {
+            *pressed = false;
+        }
     }
 
-    /// Opcode: 2NNN
-    /// Calls the subroutine at address `nnn`
-    fn op_2nnn(&mut self, nnn: usize) -> usize {
-        self.stack[self.stack_pointer] = self.program_counter;
-        self.stack_pointer += 1;
-        nnn
+    /// Places `value` on the input port, where it is read by the next FXFB
+    pub fn set_input_port(&mut self, value: u8) {
+        self.input_port = Some(value);
     }
 
-    /// Opcode: 3XNN
-    /// Skips the following instruction if `registers[x] = nn`
-    fn op_3xnn(&mut self, x: usize, nn: u8) -> usize {
-        if self.registers[x] == nn {
-            self.program_counter + INSTRUCTION_SIZE * 2
-        } else {
-            self.program_counter + INSTRUCTION_SIZE
-        }
+    /// Gets the value written to the output port by FXF8 since the last call
+    pub fn take_output_port(&mut self) -> Option<u8> {
+        self.output_port.take()
     }
 
-    /// Opcode: 4XNN
-    /// Skips the following instruction if `registers[x] != nn`
-    fn op_4xnn(&mut self, x: usize, nn: u8) -> usize {
-        if self.registers[x] != nn {
-            self.program_counter + INSTRUCTION_SIZE * 2
-        } else {
-            self.program_counter + INSTRUCTION_SIZE
-        }
+    /// Gets the variant the processor emulates
+    pub fn variant(&self) -> Variant {
+        self.variant
     }
 
-    /// Opcode: 5XY0
-    /// Skips the following instruction if `registers[x] = registers[y]`
-    fn op_5xy0(&mut self, x: usize, y: usize) -> usize {
-        if self.registers[x] == self.registers[y] {
-            self.program_counter + INSTRUCTION_SIZE * 2
-        } else {
-            self.program_counter + INSTRUCTION_SIZE
-        }
+    /// Gets the quirks the processor follows for ambiguous instructions
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
     }
 
-    /// Opcode: 6XNN
-    /// Stores `nn` in `registers[x]`
-    fn op_6xnn(&mut self, x: usize, nn: u8) -> usize {
-        self.registers[x] = nn;
-        self.program_counter + INSTRUCTION_SIZE
+    /// Gets the general purpose registers V0 to VF
+    pub fn registers(&self) -> &[u8; REGISTER_COUNT] {
+        &self.registers
     }
 
-    /// Opcode: 7XNN
-    /// Adds `nn` to `registers[x]`
-    fn op_7xnn(&mut self, x: usize, nn: u8) -> usize {
-        self.registers[x] += nn;
-        self.program_counter + INSTRUCTION_SIZE
+    /// Gets the address held by the index register I
+    pub fn index_register(&self) -> usize {
+        self.index_register
     }
 
-    /// Opcode: 8XY0
-    /// Stores `registers[y]` in `registers[x]`
-    fn op_8xy0(&mut self, x: usize, y: usize) -> usize {
-        self.registers[x] = self.registers[y];
-        self.program_counter + INSTRUCTION_SIZE
+    /// Gets the address of the next instruction
+    pub fn program_counter(&self) -> usize {
+        self.program_counter
     }
 
-    /// Opcode: 8XY1
-    /// ORs `registers[y]` with `registers[x]` and stores in `registers[x]`
-    fn op_8xy1(&mut self, x: usize, y: usize) -> usize {
-        self.registers[x] |= self.registers[y];
-        self.program_counter + INSTRUCTION_SIZE
+    /// Gets the addresses of the subroutine calls that have not returned, starting from the outermost
+    pub fn stack(&self) -> &[usize] {
+        &self.stack[..self.stack_pointer]
     }
 
-    /// Opcode: 8XY2
-    /// ANDs `registers[y]` with `registers[x]` and stores in `registers[x]`
-    fn op_8xy2(&mut self, x: usize, y: usize) -> usize {
-        self.registers[x] &= self.registers[y];
-        self.program_counter + INSTRUCTION_SIZE
+    /// Gets the value of the delay timer
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
     }
 
-    /// Opcode: 8XY3
-    /// XORs `registers[y]` with `registers[x]` and stores in `registers[x]`
-    fn op_8xy3(&mut self, x: usize, y: usize) -> usize {
-        self.registers[x] ^= self.registers[y];
-        self.program_counter + INSTRUCTION_SIZE
+    /// Gets the value of the sound timer
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
     }
 
-    /// Opcode: 8XY4
-    /// Adds `registers[y]` to `registers[x]` and stores in `registers[x]`
-    /// Sets `registers[0xF]` to `0x01` if overflow occurs and `0x00` otherwise
-    fn op_8xy4(&mut self, x: usize, y: usize) -> usize {
-        let (result, overflow) = self.registers[x].overflowing_add(self.registers[y]);
-        self.registers[x] = result;
-        self.registers[0xF] = if overflow { 0x01 } else { 0x00 };
-        self.program_counter + INSTRUCTION_SIZE
+    /// Gets the whole of memory, sized for the variant
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
     }
 
-    /// Opcode: 8XY5
-    /// Subtracts `registers[y]` from `registers[x]` and stores in `registers[x]`
-    /// Sets `registers[0xF]` to `0x01` if overflow occurs and `0x00` otherwise
-    fn op_8xy5(&mut self, x: usize, y: usize) -> usize {
-        let (result, overflow) = self.registers[x].overflowing_sub(self.registers[y]);
-        self.registers[x] = result;
-        self.registers[0xF] = if overflow { 0x01 } else { 0x00 };
-        self.program_counter + INSTRUCTION_SIZE
+    /// Returns whether `key` is held down
+    /// Keys outside `0x0..=0xF` are never held down
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.keypad.get(key as usize).is_some_and(|&pressed| pressed)
     }
 
-    /// Opcode: 8XY6
-    /// Stores `registers[y]` shifted right one bit in `registers[x]`
-    /// Sets `registers[0xF]` to the least significant bit of `registers[y]`
-    fn op_8xy6(&mut self, x: usize, y: usize) -> usize {
-        self.registers[x] = self.registers[y] >> 1;
-        self.registers[0xF] = self.registers[y] & 0x01;
-        self.program_counter + INSTRUCTION_SIZE
+    /// Replaces the source of the random numbers used by CXNN
+    pub fn set_random_source(&mut self, random: Box<dyn RandomSource>) {
+        self.random = random;
+        self.random_algorithm = None;
     }
 
-    /// Opcode: 8XY7
-    /// Subtracts `registers[x]` from `registers[y]` and stores in `registers[x]`
-    /// Sets `registers[0xF]` to `0x01` if overflow occurs and `0x00` otherwise
-    fn op_8xy7(&mut self, x: usize, y: usize) -> usize {
-        let (result, overflow) = self.registers[y].overflowing_sub(self.registers[x]);
-        self.registers[x] = result;
-        self.registers[0xF] = if overflow { 0x01 } else { 0x00 };
-        self.program_counter + INSTRUCTION_SIZE
+    /// Replaces the state of every key at once, indexed by key value
+    pub fn set_keys(&mut self, keys: [bool; KEYPAD_SIZE]) {
+        for (tapped, (&pressed, &was_pressed)) in self.tapped_keys.iter_mut().zip(keys.iter().zip(&self.keypad)) {
+            *tapped |= pressed && !was_pressed;
+        }
+        self.keypad = keys;
     }
 
-    /// Opcode: 8XYE
-    /// Stores `registers[y]` shifted left one bit in `registers[x]`
-    /// Sets `registers[0xF]` to the most significant bit of `registers[y]`
-    fn op_8xye(&mut self, x: usize, y: usize) -> usize {
-        self.registers[x] = self.registers[y] << 1;
-        self.registers[0xF] = self.registers[y] >> 7;
-        self.program_counter + INSTRUCTION_SIZE
+    /// Gets the width of the screen in pixels for the current resolution
+    pub fn screen_width(&self) -> usize {
+        if self.megachip_mode {
+            MEGA_CHIP_SCREEN_WIDTH
+        } else {
+            self.display.width()
+        }
     }
 
-    /// Opcode: 9XY0
-    /// Skips the following instruction if `registers[x] != registers[y]`
-    fn op_9xy0(&mut self, x: usize, y: usize) -> usize {
-        if self.registers[x] != self.registers[y] {
-            self.program_counter + INSTRUCTION_SIZE * 2
+    /// Gets the height of the screen in pixels for the current resolution
+    pub fn screen_height(&self) -> usize {
+        if self.megachip_mode {
+            MEGA_CHIP_SCREEN_HEIGHT
         } else {
-            self.program_counter + INSTRUCTION_SIZE
+            self.display.height()
         }
     }
 
-    /// Opcode: ANNN
-    /// Store memory address `nnn` in `index_register`
-    fn op_annn(&mut self, nnn: usize) -> usize {
-        self.index_register = nnn;
-        self.program_counter + INSTRUCTION_SIZE
+    /// Returns whether the pixel at column `x` and row `y` is lit in any plane
+    /// Coordinates outside the screen are never lit
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pixel_planes(x, y) != 0
     }
 
-    /// Opcode: BNNN
-    /// Sets the program counter to `nnn + registers[0x0]`
-    fn op_bnnn(&self, nnn: usize) -> usize {
-        nnn + (self.registers[0x0] as usize)
-    }
+    /// Gets the planes the pixel at column `x` and row `y` is lit in, with plane `n` in bit `n`
+    /// In MEGA-CHIP mode, pixels drawn in any color other than the transparent color 0 are lit in the first plane
+    /// Coordinates outside the screen are never lit
+    pub fn pixel_planes(&self, x: usize, y: usize) -> u8 {
+        if x >= self.screen_width() || y >= self.screen_height() {
+            return 0;
+        }
+        if self.megachip_mode {
+            return (self.megachip_indices[y * MEGA_CHIP_SCREEN_WIDTH + x] != 0) as u8;
+        }
 
-    /// Opcode: CXNN
-    /// Sets `registers[x]` to a random number with a mask of `nn`
-    fn op_cxnn(&mut self, x: usize, nn: u8) -> usize {
-        self.registers[x] = random::<u8>() & nn;
-        self.program_counter + INSTRUCTION_SIZE
+        self.display.planes_at(x, y)
     }
 
-    /// Opcode: DXYN
-    /// Draws a sprite from memory starting at the address `index_register` at position `registers[x]`, `registers[y]`
-    /// The sprite has a width of 8 pixels and a height of `n` pixels
-    /// Sets `registers[0xF]` to `0x01` if any previously set pixels are unset and `0x00` otherwise
-    fn op_dxyn(&mut self, x: usize, y: usize, n: u8) -> usize {
-        self.registers[0xF] = 0x00;
-        
-        let left = self.registers[x] as usize;
-        let top = self.registers[y] as usize;
+    /// Gets the display the bitplanes are drawn to
+    /// In MEGA-CHIP mode the bitplanes are hidden and `megachip_pixels` is shown instead
+    pub fn display(&self) -> &dyn Display {
+        self.display.as_ref()
+    }
 
-        for row_idx in 0..(n as usize) {
-            let row = (self.memory[self.index_register + row_idx] as u64) << ((64 - 8) - left);
-            let collision_mask = self.screen[top + row_idx] | row;
-            self.screen[top + row_idx] ^= row;
+    /// Iterates over the planes each pixel of the screen is lit in, as returned by `pixel_planes`,
+    /// in rows from the top left
+    pub fn pixels(&self) -> impl Iterator<Item = u8> + '_ {
+        let width = self.screen_width();
+        (0..self.screen_height()).flat_map(move |y| (0..width).map(move |x| self.pixel_planes(x, y)))
+    }
 
-            if self.screen[top + row_idx] != collision_mask {
-                self.registers[0xF] = 0x01;
-            }
+    /// Converts the screen to RGBA bytes in rows from the top left, coloring each pixel by the planes it is lit in
+    /// In MEGA-CHIP mode the colors are taken from `megachip_pixels` with the opacity set by 05NN instead
+    pub fn to_rgba(&self, colors: &[[u8; 4]; 1 << PLANE_COUNT]) -> Vec<u8> {
+        if self.megachip_mode {
+            return self
+                .megachip_pixels
+                .iter()
+                .flat_map(|&argb| {
+                    let [_, red, green, blue] = argb.to_be_bytes();
+                    [red, green, blue, self.screen_alpha]
+                })
+                .collect();
         }
 
-        self.program_counter + INSTRUCTION_SIZE
+        self.pixels().flat_map(|planes| colors[planes as usize]).collect()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Returns whether the 256x192 MEGA-CHIP screen is shown instead of the bitplanes
+    pub fn is_megachip_mode(&self) -> bool {
+        self.megachip_mode
+    }
 
-    #[test]
-    fn test_load() {
-        let mut chip8 = Chip8::new();
-        let buffer = [1, 2, 3, 4, 5];
+    /// Gets the ARGB color of each pixel of the MEGA-CHIP screen, in rows from the top left
+    /// Empty unless the variant is MEGA-CHIP
+    pub fn megachip_pixels(&self) -> &[u32] {
+        &self.megachip_pixels
+    }
 
-        chip8.load(&buffer);
-        assert_eq!(chip8.memory[0x200], 1);
-        assert_eq!(chip8.memory[0x201], 2);
-        assert_eq!(chip8.memory[0x202], 3);
-        assert_eq!(chip8.memory[0x203], 4);
-        assert_eq!(chip8.memory[0x204], 5);
+    /// Gets the opacity the MEGA-CHIP screen is shown with, as set by 05NN
+    pub fn screen_alpha(&self) -> u8 {
+        self.screen_alpha
     }
 
-    #[test]
-    fn test_op_00e0() {
-        let mut chip8 = Chip8::new();
-        chip8.screen = [0xFFFFFFFFFFFFFFFF; SCREEN_HEIGHT];
-        chip8.program_counter = 0x200;
-        
-        let result = chip8.op_00e0();
-        assert_eq!(chip8.screen, [0; SCREEN_HEIGHT]);
-        assert!(chip8.draw_flag);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    /// Gets the MEGA-CHIP digitized sound that is playing, as started by 060N
+    pub fn digitized_sound(&self) -> Option<&DigitizedSound> {
+        self.digitized_sound.as_ref()
     }
 
-    #[test]
-    fn test_op_00ee() {
-        let mut chip8 = Chip8::new();
-        chip8.stack_pointer = 0x01;
-        chip8.stack[0] = 0x300;
-        
-        let result = chip8.op_00ee();
-        assert_eq!(result, 0x300 + INSTRUCTION_SIZE);
-        assert_eq!(chip8.stack_pointer, 0);
+    /// Returns whether a digitized sound has been started or stopped since the last call and clears the flag
+    pub fn take_sound_changed(&mut self) -> bool {
+        std::mem::take(&mut self.sound_changed)
     }
 
-    #[test]
-    fn test_op_1nnn() {
-        let chip8 = Chip8::new();
-        assert_eq!(chip8.op_1nnn(0x400), 0x400);
+    /// Gets the CHIP-8X background color, from 0 to 3 for blue, black, green and red
+    pub fn background_color(&self) -> u8 {
+        self.background_color
     }
 
-    #[test]
-    fn test_op_2nnn() {
-        let mut chip8 = Chip8::new();
-        chip8.program_counter = 0x200;
-        chip8.stack_pointer = 0x00;
+    /// Gets the CHIP-8X foreground color of the pixel at column `x` and row `y`
+    /// The color is from 0 to 7 for black, red, blue, violet, green, yellow, aqua and white
+    /// Coordinates outside the screen use the color of the nearest zone
+    pub fn zone_color(&self, x: usize, y: usize) -> u8 {
+        let column = (x / COLOR_ZONE_WIDTH).min(COLOR_ZONE_COLUMNS - 1);
+        self.color_zones[y.min(SCREEN_HEIGHT - 1)][column]
+    }
 
-        let result = chip8.op_2nnn(0x600);
-        assert_eq!(result, 0x600);
-        assert_eq!(chip8.stack[0], 0x200);
-        assert_eq!(chip8.stack_pointer, 1);
+    /// Gets the bitplanes selected by FN01
+    fn selected_planes(&self) -> impl Iterator<Item = usize> + use<> {
+        let selected_planes = self.selected_planes;
+        (0..PLANE_COUNT).filter(move |&plane| selected_planes & (1 << plane) != 0)
     }
 
-    #[test]
-    fn test_op_3xnn() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x42;
-        chip8.program_counter = 0x200;
+    /// Moves the selected planes, or the MEGA-CHIP screen in MEGA-CHIP mode, right by `dx` and down by `dy` pixels
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        if self.megachip_mode {
+            megachip::scroll(&mut self.megachip_indices, MEGA_CHIP_SCREEN_WIDTH, dx, dy);
+            megachip::scroll(&mut self.megachip_pixels, MEGA_CHIP_SCREEN_WIDTH, dx, dy);
+        } else {
+            for plane in self.selected_planes() {
+                self.display.scroll(plane, dx, dy);
+            }
+        }
+        self.draw_flag = true;
+    }
 
-        let result = chip8.op_3xnn(0x1, 0x42);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
+    /// Switches the display to the low resolution screen, which is 64x64 in the two page mode of hi-res CHIP-8
+    fn set_lores(&mut self) {
+        let height = if self.two_page { TWO_PAGE_SCREEN_HEIGHT } else { SCREEN_HEIGHT };
+        self.display.set_resolution(SCREEN_WIDTH, height);
+    }
 
-        let result = chip8.op_3xnn(0x1, 0x43);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    /// Returns whether the screen has changed since the last call and clears `draw_flag`
+    pub fn take_draw_flag(&mut self) -> bool {
+        std::mem::take(&mut self.draw_flag)
     }
 
-    #[test]
-    fn test_op_4xnn() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x42;
-        chip8.program_counter = 0x200;
+    /// Gets the watchpoints, in the order they were added
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
 
-        let result = chip8.op_4xnn(0x1, 0x43);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
+    /// Starts recording accesses to the memory watched by `watchpoint`
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.watchpoints.push(watchpoint);
+    }
 
-        let result = chip8.op_4xnn(0x1, 0x42);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    /// Removes the watchpoint at `index` in `watchpoints` and returns it, or `None` if there is no such watchpoint
+    pub fn remove_watchpoint(&mut self, index: usize) -> Option<Watchpoint> {
+        (index < self.watchpoints.len()).then(|| self.watchpoints.remove(index))
     }
 
-    #[test]
-    fn test_op_5xy0() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x50;
-        chip8.registers[0x2] = 0x50;
-        chip8.registers[0x3] = 0x51;
-        chip8.program_counter = 0x200;
+    /// Removes every watchpoint
+    pub fn clear_watchpoints(&mut self) {
+        self.watchpoints.clear();
+    }
 
-        let result = chip8.op_5xy0(0x1, 0x2);
+    /// Gets the accesses caught by the watchpoints since the last call, in the order they were made
+    pub fn take_watch_hits(&mut self) -> Vec<WatchHit> {
+        std::mem::take(&mut self.watch_hits)
+    }
+
+    /// Captures the whole machine, including the variant, quirks and screen, as a save state
+    /// The state is versioned by `STATE_VERSION` and ends with a checksum
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+        writer.write_sized_bytes(self.variant.name().as_bytes());
+        let quirks = self.quirks;
+        for quirk in [
+            quirks.shift_uses_vy,
+            quirks.logic_resets_vf,
+            quirks.load_store_increments_index,
+            quirks.jump_uses_vx,
+            quirks.sprites_wrap,
+            quirks.display_wait,
+            quirks.lores_dxy0_8x16,
+        ] {
+            writer.write_bool(quirk);
+        }
+
+        writer.write_bytes(&self.registers);
+        writer.write_u32(self.index_register as u32);
+        writer.write_u32(self.program_counter as u32);
+        writer.write_u8(self.stack_pointer as u8);
+        for &address in &self.stack {
+            writer.write_u32(address as u32);
+        }
+        writer.write_u8(self.sound_timer);
+        writer.write_u8(self.delay_timer);
+        for &pressed in self.keypad.iter().chain(&self.tapped_keys).chain(&self.second_keypad) {
+            writer.write_bool(pressed);
+        }
+        writer.write_option(self.awaited_key.map(|key| key as u8), StateWriter::write_u8);
+
+        let (width, height) = (self.display.width(), self.display.height());
+        writer.write_u16(width as u16);
+        writer.write_u16(height as u16);
+        for plane in 0..PLANE_COUNT {
+            for y in 0..height {
+                let row = (0..width)
+                    .filter(|&x| self.display.pixel(plane, x, y))
+                    .fold(0, |row, x| row | 1 << (HIRES_SCREEN_WIDTH - 1 - x));
+                writer.write_u128(row);
+            }
+        }
+        writer.write_u8(self.selected_planes);
+        writer.write_bool(self.two_page);
+
+        writer.write_bytes(&self.flags);
+        writer.write_option(self.audio_pattern.as_ref(), |writer, pattern| writer.write_bytes(pattern));
+        writer.write_u8(self.pitch);
+        for zones in &self.color_zones {
+            writer.write_bytes(zones);
+        }
+        writer.write_u8(self.background_color);
+        writer.write_option(self.output_port, StateWriter::write_u8);
+        writer.write_option(self.input_port, StateWriter::write_u8);
+
+        writer.write_bool(self.megachip_mode);
+        writer.write_sized_bytes(&self.megachip_indices);
+        writer.write_u32(self.megachip_pixels.len() as u32);
+        for &color in self.megachip_pixels.iter().chain(&self.palette) {
+            writer.write_u32(color);
+        }
+        writer.write_option(self.font_character.map(|address| address as u32), StateWriter::write_u32);
+        writer.write_u16(self.sprite_width as u16);
+        writer.write_u16(self.sprite_height as u16);
+        writer.write_u8(self.screen_alpha);
+        writer.write_u8(self.blend_mode.code());
+        writer.write_u8(self.collision_color);
+        writer.write_option(self.digitized_sound.as_ref(), |writer, sound| {
+            writer.write_u16(sound.sample_rate);
+            writer.write_sized_bytes(&sound.samples);
+            writer.write_bool(sound.looping);
+        });
+        writer.write_option(self.random_algorithm, |writer, algorithm| {
+            writer.write_sized_bytes(algorithm.name().as_bytes())
+        });
+        writer.write_u64(self.random.state());
+        writer.write_bool(self.awaiting_vblank);
+        // Memory comes last because its size in the state depends on its contents
+        writer.write_sparse_bytes(&self.memory);
+        writer.finish()
+    }
+
+    /// Restores a save state created by `save_state`, replacing the variant and quirks along with the rest of the machine
+    /// The display keeps its implementation but takes its contents from the state
+    /// The random number source switches to the algorithm saved in the state if that differs from the current one,
+    /// and otherwise keeps its implementation, including one given to `set_random_source`, and takes its state
+    /// The processor is left untouched if the state cannot be read
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut reader = StateReader::new(data)?;
+        let (mut restored, memory) = Self::read_state(&mut reader)?;
+        reader.finish()?;
+
+        // The state is fully read, so the memory of the processor can be reused instead of allocating up to 16 MiB
+        let mut buffer = std::mem::take(&mut self.memory);
+        buffer.resize(memory.len(), 0);
+        memory.copy_to(&mut buffer);
+        restored.memory = buffer;
+
+        let (width, height) = (restored.display.width(), restored.display.height());
+        self.display.set_resolution(width, height);
+        for plane in 0..PLANE_COUNT {
+            for y in 0..height {
+                for x in 0..width {
+                    self.display.set_pixel(plane, x, y, restored.display.pixel(plane, x, y));
+                }
+            }
+        }
+        std::mem::swap(&mut self.display, &mut restored.display);
+        if restored.random_algorithm.is_none() || restored.random_algorithm == self.random_algorithm {
+            let random_state = restored.random.state();
+            std::mem::swap(&mut self.random, &mut restored.random);
+            restored.random.set_state(random_state);
+            restored.random_algorithm = self.random_algorithm;
+        }
+        // Watchpoints belong to whoever is debugging the program rather than to the saved machine
+        restored.watchpoints = std::mem::take(&mut self.watchpoints);
+        *self = restored;
+        Ok(())
+    }
+
+    /// Reads the contents of a save state into a new processor drawing to a `Bitmap`
+    /// The random number source uses the saved algorithm, or the default one if the state was saved with a custom source
+    /// The memory is returned separately for the caller to copy into a buffer, leaving the processor with none
+    fn read_state<'a>(reader: &mut StateReader<'a>) -> Result<(Chip8, SparseBytes<'a>), StateError> {
+        let variant = std::str::from_utf8(reader.read_sized_bytes()?)
+            .ok()
+            .and_then(Variant::from_name)
+            .ok_or(StateError::Malformed)?;
+        let quirks = Quirks {
+            shift_uses_vy: reader.read_bool()?,
+            logic_resets_vf: reader.read_bool()?,
+            load_store_increments_index: reader.read_bool()?,
+            jump_uses_vx: reader.read_bool()?,
+            sprites_wrap: reader.read_bool()?,
+            display_wait: reader.read_bool()?,
+            lores_dxy0_8x16: reader.read_bool()?,
+        };
+        let config = Config {
+            variant,
+            quirks,
+            ..Config::default()
+        };
+        let mut chip8 = Chip8::with_memory(config, Box::new(Bitmap::new()), Vec::new());
+        let valid = |condition: bool| if condition { Ok(()) } else { Err(StateError::Malformed) };
+
+        chip8.registers = reader.read_array()?;
+        chip8.index_register = reader.read_u32()? as usize;
+        chip8.program_counter = reader.read_u32()? as usize;
+        chip8.stack_pointer = reader.read_u8()? as usize;
+        valid(chip8.stack_pointer <= variant.stack_depth())?;
+        for address in &mut chip8.stack {
+            *address = reader.read_u32()? as usize;
+        }
+        chip8.sound_timer = reader.read_u8()?;
+        chip8.delay_timer = reader.read_u8()?;
+        for pressed in chip8.keypad.iter_mut().chain(&mut chip8.tapped_keys).chain(&mut chip8.second_keypad) {
+            *pressed = reader.read_bool()?;
+        }
+        chip8.awaited_key = reader.read_option(StateReader::read_u8)?.map(|key| key as usize);
+        valid(chip8.awaited_key.is_none_or(|key| key < KEYPAD_SIZE))?;
+
+        let width = reader.read_u16()? as usize;
+        let height = reader.read_u16()? as usize;
+        valid(matches!(
+            (width, height),
+            (SCREEN_WIDTH, SCREEN_HEIGHT | TWO_PAGE_SCREEN_HEIGHT) | (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT)
+        ))?;
+        chip8.display.set_resolution(width, height);
+        for plane in 0..PLANE_COUNT {
+            for y in 0..height {
+                let row = reader.read_u128()?;
+                for x in 0..width {
+                    chip8.display.set_pixel(plane, x, y, row & 1 << (HIRES_SCREEN_WIDTH - 1 - x) != 0);
+                }
+            }
+        }
+        chip8.selected_planes = reader.read_u8()?;
+        valid(chip8.selected_planes < 1 << PLANE_COUNT)?;
+        chip8.two_page = reader.read_bool()?;
+
+        chip8.flags = reader.read_array()?;
+        chip8.audio_pattern = reader.read_option(StateReader::read_array)?;
+        chip8.pitch = reader.read_u8()?;
+        for zones in &mut chip8.color_zones {
+            *zones = reader.read_array()?;
+        }
+        valid(chip8.color_zones.iter().flatten().all(|&color| color <= 0x07))?;
+        chip8.background_color = reader.read_u8()?;
+        valid(chip8.background_color < BACKGROUND_COLOR_COUNT)?;
+        chip8.output_port = reader.read_option(StateReader::read_u8)?;
+        chip8.input_port = reader.read_option(StateReader::read_u8)?;
+
+        chip8.megachip_mode = reader.read_bool()?;
+        valid(!chip8.megachip_mode || variant.has_megachip_instructions())?;
+        let indices = reader.read_sized_bytes()?;
+        valid(indices.len() == chip8.megachip_indices.len())?;
+        chip8.megachip_indices.copy_from_slice(indices);
+        valid(reader.read_u32()? as usize == chip8.megachip_pixels.len())?;
+        for color in chip8.megachip_pixels.iter_mut().chain(&mut chip8.palette) {
+            *color = reader.read_u32()?;
+        }
+        chip8.font_character = reader.read_option(StateReader::read_u32)?.map(|address| address as usize);
+        chip8.sprite_width = reader.read_u16()? as usize;
+        chip8.sprite_height = reader.read_u16()? as usize;
+        valid((1..=MAX_SPRITE_SIZE).contains(&chip8.sprite_width))?;
+        valid((1..=MAX_SPRITE_SIZE).contains(&chip8.sprite_height))?;
+        chip8.screen_alpha = reader.read_u8()?;
+        chip8.blend_mode = BlendMode::from_code(reader.read_u8()?).ok_or(StateError::Malformed)?;
+        chip8.collision_color = reader.read_u8()?;
+        chip8.digitized_sound = reader.read_option(|reader| {
+            Ok(DigitizedSound {
+                sample_rate: reader.read_u16()?,
+                samples: reader.read_sized_bytes()?.to_vec(),
+                looping: reader.read_bool()?,
+            })
+        })?;
+        chip8.random_algorithm = reader.read_option(|reader| {
+            std::str::from_utf8(reader.read_sized_bytes()?)
+                .ok()
+                .and_then(RandomAlgorithm::from_name)
+                .ok_or(StateError::Malformed)
+        })?;
+        chip8.random = chip8.random_algorithm.unwrap_or_default().source(0);
+        chip8.random.set_state(reader.read_u64()?);
+        chip8.awaiting_vblank = reader.read_bool()?;
+        let memory = reader.read_sparse_bytes()?;
+        valid(memory.len() == variant.memory_size())?;
+
+        chip8.draw_flag = true;
+        chip8.sound_changed = true;
+        Ok((chip8, memory))
+    }
+
+    /// Emulates one frame, running up to `cycles` instructions and then ticking the timers once
+    /// Stops early if the program halts or an instruction hits a watchpoint that stops execution,
+    /// or after drawing a sprite if `quirks.display_wait` is enabled
+    /// Calling this at `TIMER_FREQUENCY` keeps the timers correct regardless of the instruction rate
+    pub fn run_frame(&mut self, cycles: usize) -> Result<CycleOutcome, Chip8Error> {
+        let (outcome, _) = self.run_cycles(cycles)?;
+        self.tick_timers();
+        Ok(outcome)
+    }
+
+    /// Runs up to `cycles` instructions of a frame without ticking the timers,
+    /// returning the outcome of the last one and the number of cycles emulated
+    /// Stops early in the same cases as `run_frame`
+    pub fn run_cycles(&mut self, cycles: usize) -> Result<(CycleOutcome, usize), Chip8Error> {
+        let mut outcome = CycleOutcome::Executed;
+        for cycle in 0..cycles {
+            outcome = self.emulate_cycle()?;
+            let stopped = matches!(outcome, CycleOutcome::Halted | CycleOutcome::Watchpoint);
+            if stopped || std::mem::take(&mut self.awaiting_vblank) {
+                return Ok((outcome, cycle + 1));
+            }
+        }
+        Ok((outcome, cycles))
+    }
+
+    /// Decrements the delay and sound timers if they are above zero
+    /// Should be called at `TIMER_FREQUENCY`
+    pub fn tick_timers(&mut self) {
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+    }
+
+    /// Returns whether the buzzer is sounding, which is whenever `sound_timer` is above zero
+    pub fn is_sound_playing(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    /// Gets the 1-bit samples the buzzer plays, starting from the most significant bit of the first byte
+    /// Returns `None` until the program loads a pattern with F002, in which case the buzzer plays a plain tone
+    pub fn audio_pattern(&self) -> Option<&[u8; AUDIO_PATTERN_SIZE]> {
+        self.audio_pattern.as_ref()
+    }
+
+    /// Gets the rate `audio_pattern` is played at in bits per second, as set by FX3A
+    pub fn audio_sample_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - DEFAULT_PITCH as f32) / 48.0)
+    }
+
+    /// Emulates one tick of the processor
+    /// On error the program counter is left pointing at the faulting instruction
+    pub fn emulate_cycle(&mut self) -> Result<CycleOutcome, Chip8Error> {
+        // An instruction that hit a stopping watchpoint and then faulted must not stop the next one
+        self.watch_stopped = false;
+        let opcode = self.fetch_opcode()?;
+        let pc = self.program_counter;
+        let instruction = self.decode(pc, opcode).ok_or(Fault::UnknownOpcode.at(pc, opcode))?;
+
+        let next_program_counter = match instruction {
+            Instruction::Op0010 => Ok(self.op_0010()),
+            Instruction::Op0011 => Ok(self.op_0011()),
+            Instruction::Op01nn(nn) => self.op_01nn(nn),
+            Instruction::Op02nn(nn) => self.op_02nn(nn),
+            Instruction::Op03nn(nn) => Ok(self.op_03nn(nn)),
+            Instruction::Op04nn(nn) => Ok(self.op_04nn(nn)),
+            Instruction::Op05nn(nn) => Ok(self.op_05nn(nn)),
+            Instruction::Op060n(n) => self.op_060n(n),
+            Instruction::Op07nn => Ok(self.op_07nn()),
+            Instruction::Op080n(n) => self.op_080n(n),
+            Instruction::Op09nn(nn) => Ok(self.op_09nn(nn)),
+            Instruction::Op0230 => Ok(self.op_0230()),
+            Instruction::Op02a0 => Ok(self.op_02a0()),
+            Instruction::Op00bn(n) => Ok(self.op_00bn(n)),
+            Instruction::Op00cn(n) => Ok(self.op_00cn(n)),
+            Instruction::Op00dn(n) => Ok(self.op_00dn(n)),
+            Instruction::Op00e0 => Ok(self.op_00e0()),
+            Instruction::Op00ee => self.op_00ee(),
+            Instruction::Op00fb => Ok(self.op_00fb()),
+            Instruction::Op00fc => Ok(self.op_00fc()),
+            Instruction::Op00fd => Ok(self.op_00fd()),
+            Instruction::Op00fe => Ok(self.op_00fe()),
+            Instruction::Op00ff => Ok(self.op_00ff()),
+            Instruction::Op1260 => Ok(self.op_1260()),
+            Instruction::Op1nnn(nnn) => Ok(self.op_1nnn(nnn)),
+            Instruction::Op2nnn(nnn) => self.op_2nnn(nnn),
+            Instruction::Op3xnn(x, nn) => Ok(self.op_3xnn(x, nn)),
+            Instruction::Op4xnn(x, nn) => Ok(self.op_4xnn(x, nn)),
+            Instruction::Op5xy0(x, y) => Ok(self.op_5xy0(x, y)),
+            Instruction::Op5xy1(x, y) => Ok(self.op_5xy1(x, y)),
+            Instruction::Op5xy2(x, y) => self.op_5xy2(x, y),
+            Instruction::Op5xy3(x, y) => self.op_5xy3(x, y),
+            Instruction::Op6xnn(x, nn) => Ok(self.op_6xnn(x, nn)),
+            Instruction::Op7xnn(x, nn) => Ok(self.op_7xnn(x, nn)),
+            Instruction::Op8xy0(x, y) => Ok(self.op_8xy0(x, y)),
+            Instruction::Op8xy1(x, y) => Ok(self.op_8xy1(x, y)),
+            Instruction::Op8xy2(x, y) => Ok(self.op_8xy2(x, y)),
+            Instruction::Op8xy3(x, y) => Ok(self.op_8xy3(x, y)),
+            Instruction::Op8xy4(x, y) => Ok(self.op_8xy4(x, y)),
+            Instruction::Op8xy5(x, y) => Ok(self.op_8xy5(x, y)),
+            Instruction::Op8xy6(x, y) => Ok(self.op_8xy6(x, y)),
+            Instruction::Op8xy7(x, y) => Ok(self.op_8xy7(x, y)),
+            Instruction::Op8xye(x, y) => Ok(self.op_8xye(x, y)),
+            Instruction::Op9xy0(x, y) => Ok(self.op_9xy0(x, y)),
+            Instruction::OpAnnn(nnn) => Ok(self.op_annn(nnn)),
+            Instruction::OpBxy0(x, y) => Ok(self.op_bxy0(x, y)),
+            Instruction::OpBxyn(x, y, n) => Ok(self.op_bxyn(x, y, n)),
+            Instruction::OpBnnn(nnn) => Ok(self.op_bnnn(nnn)),
+            Instruction::OpCxnn(x, nn) => Ok(self.op_cxnn(x, nn)),
+            Instruction::OpDxyn(x, y, n) => self.op_dxyn(x, y, n),
+            Instruction::OpEx9e(x) => Ok(self.op_ex9e(x)),
+            Instruction::OpExa1(x) => Ok(self.op_exa1(x)),
+            Instruction::OpExf2(x) => Ok(self.op_exf2(x)),
+            Instruction::OpExf5(x) => Ok(self.op_exf5(x)),
+            Instruction::OpF000 => self.op_f000(),
+            Instruction::OpFn01(n) => Ok(self.op_fn01(n)),
+            Instruction::OpF002 => self.op_f002(),
+            Instruction::OpFx07(x) => Ok(self.op_fx07(x)),
+            Instruction::OpFx0a(x) => Ok(self.op_fx0a(x)),
+            Instruction::OpFx15(x) => Ok(self.op_fx15(x)),
+            Instruction::OpFx18(x) => Ok(self.op_fx18(x)),
+            Instruction::OpFx1e(x) => Ok(self.op_fx1e(x)),
+            Instruction::OpFx29(x) => Ok(self.op_fx29(x)),
+            Instruction::OpFx30(x) => Ok(self.op_fx30(x)),
+            Instruction::OpFx33(x) => self.op_fx33(x),
+            Instruction::OpFx3a(x) => Ok(self.op_fx3a(x)),
+            Instruction::OpFx55(x) => self.op_fx55(x),
+            Instruction::OpFx65(x) => self.op_fx65(x),
+            Instruction::OpFx75(x) => Ok(self.op_fx75(x)),
+            Instruction::OpFx85(x) => Ok(self.op_fx85(x)),
+            Instruction::OpFxf8(x) => Ok(self.op_fxf8(x)),
+            Instruction::OpFxfb(x) => Ok(self.op_fxfb(x)),
+        }
+        .map_err(|fault| fault.at(pc, opcode))?;
+        self.tapped_keys = [false; KEYPAD_SIZE];
+
+        let stalled = next_program_counter == pc;
+        let outcome = match instruction {
+            Instruction::Op00fd => CycleOutcome::Halted,
+            Instruction::Op1nnn(_) if stalled => CycleOutcome::Halted,
+            Instruction::OpFx0a(_) if stalled => CycleOutcome::WaitingForKey,
+            Instruction::OpFxfb(_) if stalled => CycleOutcome::WaitingForInput,
+            _ if self.watch_stopped => CycleOutcome::Watchpoint,
+            _ => CycleOutcome::Executed,
+        };
+
+        self.program_counter = next_program_counter;
+        Ok(outcome)
+    }
+
+    /// Decodes `opcode` as it would be at `address` under the current variant,
+    /// or returns `None` if the variant has no such instruction
+    fn decode(&self, address: usize, opcode: Opcode) -> Option<Instruction> {
+        let superchip = self.variant.has_superchip_instructions();
+        let xochip = self.variant.has_xochip_instructions();
+        let chip8x = self.variant.has_chip8x_instructions();
+        let megachip = self.variant.has_megachip_instructions();
+        let two_page_start = self.variant.has_two_page_mode() && address == PROGRAM_START_ADDRESS;
+        let (x, y, n, nn, nnn) = (opcode.x(), opcode.y(), opcode.n(), opcode.nn(), opcode.nnn());
+        let instruction = match opcode.nibbles() {
+            (0x00, 0x00, 0x01, 0x00) if megachip => Instruction::Op0010,
+            (0x00, 0x00, 0x01, 0x01) if megachip => Instruction::Op0011,
+            (0x00, 0x01, _, _) if megachip => Instruction::Op01nn(nn),
+            (0x00, 0x02, _, _) if megachip => Instruction::Op02nn(nn),
+            (0x00, 0x03, _, _) if megachip => Instruction::Op03nn(nn),
+            (0x00, 0x04, _, _) if megachip => Instruction::Op04nn(nn),
+            (0x00, 0x05, _, _) if megachip => Instruction::Op05nn(nn),
+            (0x00, 0x06, 0x00, _) if megachip => Instruction::Op060n(n),
+            (0x00, 0x07, _, _) if megachip => Instruction::Op07nn,
+            (0x00, 0x08, 0x00, _) if megachip => Instruction::Op080n(n),
+            (0x00, 0x09, _, _) if megachip => Instruction::Op09nn(nn),
+            (0x00, 0x02, 0x03, 0x00) if self.two_page => Instruction::Op0230,
+            (0x00, 0x02, 0x0A, 0x00) if chip8x => Instruction::Op02a0,
+            (0x00, 0x00, 0x0B, _) if megachip => Instruction::Op00bn(n),
+            (0x00, 0x00, 0x0C, _) if superchip => Instruction::Op00cn(n),
+            (0x00, 0x00, 0x0D, _) if xochip => Instruction::Op00dn(n),
+            (0x00, 0x00, 0x0E, 0x00) => Instruction::Op00e0,
+            (0x00, 0x00, 0x0E, 0x0E) => Instruction::Op00ee,
+            (0x00, 0x00, 0x0F, 0x0B) if superchip => Instruction::Op00fb,
+            (0x00, 0x00, 0x0F, 0x0C) if superchip => Instruction::Op00fc,
+            (0x00, 0x00, 0x0F, 0x0D) if superchip => Instruction::Op00fd,
+            (0x00, 0x00, 0x0F, 0x0E) if superchip => Instruction::Op00fe,
+            (0x00, 0x00, 0x0F, 0x0F) if superchip => Instruction::Op00ff,
+            (0x01, 0x02, 0x06, 0x00) if two_page_start => Instruction::Op1260,
+            (0x01, _, _, _) => Instruction::Op1nnn(nnn),
+            (0x02, _, _, _) => Instruction::Op2nnn(nnn),
+            (0x03, _, _, _) => Instruction::Op3xnn(x, nn),
+            (0x04, _, _, _) => Instruction::Op4xnn(x, nn),
+            (0x05, _, _, 0x00) => Instruction::Op5xy0(x, y),
+            (0x05, _, _, 0x01) if chip8x => Instruction::Op5xy1(x, y),
+            (0x05, _, _, 0x02) if xochip => Instruction::Op5xy2(x, y),
+            (0x05, _, _, 0x03) if xochip => Instruction::Op5xy3(x, y),
+            (0x06, _, _, _) => Instruction::Op6xnn(x, nn),
+            (0x07, _, _, _) => Instruction::Op7xnn(x, nn),
+            (0x08, _, _, 0x00) => Instruction::Op8xy0(x, y),
+            (0x08, _, _, 0x01) => Instruction::Op8xy1(x, y),
+            (0x08, _, _, 0x02) => Instruction::Op8xy2(x, y),
+            (0x08, _, _, 0x03) => Instruction::Op8xy3(x, y),
+            (0x08, _, _, 0x04) => Instruction::Op8xy4(x, y),
+            (0x08, _, _, 0x05) => Instruction::Op8xy5(x, y),
+            (0x08, _, _, 0x06) => Instruction::Op8xy6(x, y),
+            (0x08, _, _, 0x07) => Instruction::Op8xy7(x, y),
+            (0x08, _, _, 0x0E) => Instruction::Op8xye(x, y),
+            (0x09, _, _, 0x00) => Instruction::Op9xy0(x, y),
+            (0x0A, _, _, _) => Instruction::OpAnnn(nnn),
+            (0x0B, _, _, 0x00) if chip8x => Instruction::OpBxy0(x, y),
+            (0x0B, _, _, _) if chip8x => Instruction::OpBxyn(x, y, n),
+            (0x0B, _, _, _) => Instruction::OpBnnn(nnn),
+            (0x0C, _, _, _) => Instruction::OpCxnn(x, nn),
+            (0x0D, _, _, _) => Instruction::OpDxyn(x, y, n),
+            (0x0E, _, 0x09, 0x0E) => Instruction::OpEx9e(x),
+            (0x0E, _, 0x0A, 0x01) => Instruction::OpExa1(x),
+            (0x0E, _, 0x0F, 0x02) if chip8x => Instruction::OpExf2(x),
+            (0x0E, _, 0x0F, 0x05) if chip8x => Instruction::OpExf5(x),
+            (0x0F, 0x00, 0x00, 0x00) if xochip => Instruction::OpF000,
+            (0x0F, _, 0x00, 0x01) if xochip => Instruction::OpFn01(x as u8),
+            (0x0F, 0x00, 0x00, 0x02) if xochip => Instruction::OpF002,
+            (0x0F, _, 0x00, 0x07) => Instruction::OpFx07(x),
+            (0x0F, _, 0x00, 0x0A) => Instruction::OpFx0a(x),
+            (0x0F, _, 0x01, 0x05) => Instruction::OpFx15(x),
+            (0x0F, _, 0x01, 0x08) => Instruction::OpFx18(x),
+            (0x0F, _, 0x01, 0x0E) => Instruction::OpFx1e(x),
+            (0x0F, _, 0x02, 0x09) => Instruction::OpFx29(x),
+            (0x0F, _, 0x03, 0x00) if superchip => Instruction::OpFx30(x),
+            (0x0F, _, 0x03, 0x03) => Instruction::OpFx33(x),
+            (0x0F, _, 0x03, 0x0A) if xochip => Instruction::OpFx3a(x),
+            (0x0F, _, 0x05, 0x05) => Instruction::OpFx55(x),
+            (0x0F, _, 0x06, 0x05) => Instruction::OpFx65(x),
+            (0x0F, _, 0x07, 0x05) if superchip => Instruction::OpFx75(x),
+            (0x0F, _, 0x08, 0x05) if superchip => Instruction::OpFx85(x),
+            (0x0F, _, 0x0F, 0x08) if chip8x => Instruction::OpFxf8(x),
+            (0x0F, _, 0x0F, 0x0B) if chip8x => Instruction::OpFxfb(x),
+            _ => return None,
+        };
+        Some(instruction)
+    }
+
+    /// Describes `opcode` as it would be decoded at `address`, as the opcode pattern of the instruction
+    /// and an explanation of what it does under the current variant, mode and quirks
+    /// Returns `None` if the variant has no such instruction
+    pub fn describe_instruction(&self, address: usize, opcode: u16) -> Option<(&'static str, &'static str)> {
+        let instruction = self.decode(address, opcode)?;
+        Some((instruction.pattern(), instruction.explain(&self.quirks, self.megachip_mode)))
+    }
+
+    /// Fetches the 16 bit opcode pointed to by the program counter
+    fn fetch_opcode(&self) -> Result<Opcode, Chip8Error> {
+        let pc = self.program_counter;
+        let high = self.fetch_byte(pc).map_err(|fault| fault.at(pc, 0x0000))?;
+        let low = self.fetch_byte(pc + 1).map_err(|fault| fault.at(pc, (high as u16) << 8))?;
+        Ok(((high as u16) << 8) | (low as u16))
+    }
+
+    /// Reads the byte of an instruction at `address`, which watchpoints do not catch
+    fn fetch_byte(&self, address: usize) -> Result<u8, Fault> {
+        self.memory.get(address).copied().ok_or(Fault::MemoryOutOfBounds(address))
+    }
+
+    /// Reads the byte at `address`
+    fn read_memory(&mut self, address: usize) -> Result<u8, Fault> {
+        let value = self.fetch_byte(address)?;
+        self.watch(address..address + 1, Access::Read);
+        Ok(value)
+    }
+
+    /// Writes `value` to the byte at `address`
+    fn write_memory(&mut self, address: usize, value: u8) -> Result<(), Fault> {
+        let byte = self.memory.get_mut(address).ok_or(Fault::MemoryOutOfBounds(address))?;
+        *byte = value;
+        self.watch(address..address + 1, Access::Write);
+        Ok(())
+    }
+
+    /// Records a hit for every byte of `addresses` caught by a watchpoint for `access`,
+    /// with the value the byte has after the access
+    fn watch(&mut self, addresses: Range<usize>, access: Access) {
+        if self.watchpoints.is_empty() {
+            return;
+        }
+
+        for address in addresses {
+            let mut caught = self
+                .watchpoints
+                .iter()
+                .filter(|watchpoint| watchpoint.watches(access) && watchpoint.addresses.contains(&address))
+                .peekable();
+            if caught.peek().is_none() {
+                continue;
+            }
+
+            self.watch_stopped |= caught.any(|watchpoint| watchpoint.stops);
+            self.watch_hits.push(WatchHit {
+                pc: self.program_counter,
+                address,
+                access,
+                value: self.memory[address],
+            });
+        }
+    }
+
+    /// Gets the address of the instruction after the next one, which the conditional skips jump to
+    /// The four byte F000 NNNN instruction of XO-CHIP is skipped as a whole
+    /// as is the four byte 01NN NNNN instruction of MEGA-CHIP
+    fn skipped_program_counter(&self) -> usize {
+        let next_program_counter = self.program_counter + INSTRUCTION_SIZE;
+        let next_opcode = self.memory.get(next_program_counter..next_program_counter + INSTRUCTION_SIZE);
+        let next_is_long = match next_opcode {
+            Some(&[0xF0, 0x00]) => self.variant.has_xochip_instructions(),
+            Some(&[0x01, _]) => self.variant.has_megachip_instructions(),
+            _ => false,
+        };
+
+        if next_is_long {
+            next_program_counter + INSTRUCTION_SIZE * 2
+        } else {
+            next_program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: 0010
+    fn op_0010(&mut self) -> usize {
+        self.megachip_mode = false;
+        self.op_00e0()
+    }
+
+    /// Opcode: 0011
+    fn op_0011(&mut self) -> usize {
+        self.megachip_mode = true;
+        self.op_00e0()
+    }
+
+    /// Opcode: 01NN NNNN
+    fn op_01nn(&mut self, nn: u8) -> Result<usize, Fault> {
+        let high = self.fetch_byte(self.program_counter + INSTRUCTION_SIZE)?;
+        let low = self.fetch_byte(self.program_counter + INSTRUCTION_SIZE + 1)?;
+        self.index_register = u32::from_be_bytes([0, nn, high, low]) as usize;
+        Ok(self.program_counter + INSTRUCTION_SIZE * 2)
+    }
+
+    /// Opcode: 02NN
+    fn op_02nn(&mut self, nn: u8) -> Result<usize, Fault> {
+        for color in 1..=nn as usize {
+            let address = self.index_register + (color - 1) * 4;
+            let mut argb = [0; 4];
+            for (offset, byte) in argb.iter_mut().enumerate() {
+                *byte = self.read_memory(address + offset)?;
+            }
+            self.palette[color] = u32::from_be_bytes(argb);
+        }
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: 03NN
+    fn op_03nn(&mut self, nn: u8) -> usize {
+        self.sprite_width = if nn == 0 { MAX_SPRITE_SIZE } else { nn as usize };
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 04NN
+    fn op_04nn(&mut self, nn: u8) -> usize {
+        self.sprite_height = if nn == 0 { MAX_SPRITE_SIZE } else { nn as usize };
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 05NN
+    fn op_05nn(&mut self, nn: u8) -> usize {
+        self.screen_alpha = nn;
+        self.draw_flag = true;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 060N
+    /// The sound starts with a 16 bit sample rate, a 24 bit sample count and a reserved byte, followed by the samples
+    fn op_060n(&mut self, n: u8) -> Result<usize, Fault> {
+        let mut header = [0; DIGITIZED_SOUND_HEADER_SIZE];
+        for (offset, byte) in header.iter_mut().enumerate() {
+            *byte = self.read_memory(self.index_register + offset)?;
+        }
+        let sample_rate = u16::from_be_bytes([header[0], header[1]]);
+        let length = u32::from_be_bytes([0, header[2], header[3], header[4]]) as usize;
+
+        let start = self.index_register + DIGITIZED_SOUND_HEADER_SIZE;
+        let samples = self
+            .memory
+            .get(start..start + length)
+            .ok_or(Fault::MemoryOutOfBounds(start.max(self.memory.len())))?
+            .to_vec();
+        self.watch(start..start + length, Access::Read);
+
+        self.digitized_sound = Some(DigitizedSound {
+            sample_rate,
+            samples,
+            looping: n == 0,
+        });
+        self.sound_changed = true;
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: 07NN
+    fn op_07nn(&mut self) -> usize {
+        self.digitized_sound = None;
+        self.sound_changed = true;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 080N
+    fn op_080n(&mut self, n: u8) -> Result<usize, Fault> {
+        self.blend_mode = BlendMode::from_code(n).ok_or(Fault::UnknownOpcode)?;
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: 09NN
+    fn op_09nn(&mut self, nn: u8) -> usize {
+        self.collision_color = nn;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 0230
+    fn op_0230(&mut self) -> usize {
+        self.op_00e0()
+    }
+
+    /// Opcode: 02A0
+    fn op_02a0(&mut self) -> usize {
+        self.background_color = (self.background_color + 1) % BACKGROUND_COLOR_COUNT;
+        self.draw_flag = true;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 00BN
+    fn op_00bn(&mut self, n: u8) -> usize {
+        self.scroll(0, -(n as isize));
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 00CN
+    fn op_00cn(&mut self, n: u8) -> usize {
+        self.scroll(0, n as isize);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 00DN
+    fn op_00dn(&mut self, n: u8) -> usize {
+        self.scroll(0, -(n as isize));
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 00E0
+    fn op_00e0(&mut self) -> usize {
+        if self.megachip_mode {
+            self.megachip_indices.fill(0);
+            self.megachip_pixels.fill(0);
+        } else {
+            for plane in self.selected_planes() {
+                self.display.clear(plane);
+            }
+        }
+        self.draw_flag = true;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 00EE
+    fn op_00ee(&mut self) -> Result<usize, Fault> {
+        if self.stack_pointer == 0 {
+            return Err(Fault::StackUnderflow);
+        }
+        self.stack_pointer -= 1;
+        Ok(self.stack[self.stack_pointer] + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: 00FB
+    fn op_00fb(&mut self) -> usize {
+        self.scroll(4, 0);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 00FC
+    fn op_00fc(&mut self) -> usize {
+        self.scroll(-4, 0);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 00FD
+    /// The program counter does not advance, so the processor stays halted
+    fn op_00fd(&self) -> usize {
+        self.program_counter
+    }
+
+    /// Opcode: 00FE
+    fn op_00fe(&mut self) -> usize {
+        self.set_lores();
+        self.op_00e0()
+    }
+
+    /// Opcode: 00FF
+    fn op_00ff(&mut self) -> usize {
+        self.display.set_resolution(HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+        self.op_00e0()
+    }
+
+    /// Opcode: 1260 at `PROGRAM_START_ADDRESS`
+    /// Continues at `TWO_PAGE_PROGRAM_ADDRESS`, past the patched interpreter
+    fn op_1260(&mut self) -> usize {
+        self.two_page = true;
+        self.set_lores();
+        self.draw_flag = true;
+        TWO_PAGE_PROGRAM_ADDRESS
+    }
+
+    /// Opcode: 1NNN
+    fn op_1nnn(&self, nnn: usize) -> usize {
+        nnn
+    }
+
+    /// Opcode: 2NNN
+    fn op_2nnn(&mut self, nnn: usize) -> Result<usize, Fault> {
+        if self.stack_pointer >= self.variant.stack_depth() {
+            return Err(Fault::StackOverflow);
+        }
+        self.stack[self.stack_pointer] = self.program_counter;
+        self.stack_pointer += 1;
+        Ok(nnn)
+    }
+
+    /// Opcode: 3XNN
+    fn op_3xnn(&mut self, x: usize, nn: u8) -> usize {
+        if self.registers[x] == nn {
+            self.skipped_program_counter()
+        } else {
+            self.program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: 4XNN
+    fn op_4xnn(&mut self, x: usize, nn: u8) -> usize {
+        if self.registers[x] != nn {
+            self.skipped_program_counter()
+        } else {
+            self.program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: 5XY0
+    fn op_5xy0(&mut self, x: usize, y: usize) -> usize {
+        if self.registers[x] == self.registers[y] {
+            self.skipped_program_counter()
+        } else {
+            self.program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: 5XY1
+    fn op_5xy1(&mut self, x: usize, y: usize) -> usize {
+        self.registers[x] = ((self.registers[x] & 0x77) + (self.registers[y] & 0x77)) & 0x77;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 5XY2
+    /// The registers are stored in descending order if `x` is greater than `y`, and `index_register` is unchanged
+    fn op_5xy2(&mut self, x: usize, y: usize) -> Result<usize, Fault> {
+        for offset in 0..=x.abs_diff(y) {
+            let register = if x <= y { x + offset } else { x - offset };
+            self.write_memory(self.index_register + offset, self.registers[register])?;
+        }
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: 5XY3
+    /// The registers are loaded in descending order if `x` is greater than `y`, and `index_register` is unchanged
+    fn op_5xy3(&mut self, x: usize, y: usize) -> Result<usize, Fault> {
+        for offset in 0..=x.abs_diff(y) {
+            let register = if x <= y { x + offset } else { x - offset };
+            self.registers[register] = self.read_memory(self.index_register + offset)?;
+        }
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: 6XNN
+    fn op_6xnn(&mut self, x: usize, nn: u8) -> usize {
+        self.registers[x] = nn;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 7XNN
+    fn op_7xnn(&mut self, x: usize, nn: u8) -> usize {
+        self.registers[x] = self.registers[x].wrapping_add(nn);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XY0
+    fn op_8xy0(&mut self, x: usize, y: usize) -> usize {
+        self.registers[x] = self.registers[y];
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XY1
+    fn op_8xy1(&mut self, x: usize, y: usize) -> usize {
+        self.registers[x] |= self.registers[y];
+        if self.quirks.logic_resets_vf {
+            self.registers[0xF] = 0x00;
+        }
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XY2
+    fn op_8xy2(&mut self, x: usize, y: usize) -> usize {
+        self.registers[x] &= self.registers[y];
+        if self.quirks.logic_resets_vf {
+            self.registers[0xF] = 0x00;
+        }
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XY3
+    fn op_8xy3(&mut self, x: usize, y: usize) -> usize {
+        self.registers[x] ^= self.registers[y];
+        if self.quirks.logic_resets_vf {
+            self.registers[0xF] = 0x00;
+        }
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XY4
+    fn op_8xy4(&mut self, x: usize, y: usize) -> usize {
+        let (result, overflow) = self.registers[x].overflowing_add(self.registers[y]);
+        self.registers[x] = result;
+        self.registers[0xF] = if overflow { 0x01 } else { 0x00 };
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XY5
+    fn op_8xy5(&mut self, x: usize, y: usize) -> usize {
+        let (result, overflow) = self.registers[x].overflowing_sub(self.registers[y]);
+        self.registers[x] = result;
+        self.registers[0xF] = if overflow { 0x00 } else { 0x01 };
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XY6
+    fn op_8xy6(&mut self, x: usize, y: usize) -> usize {
+        let value = self.registers[if self.quirks.shift_uses_vy { y } else { x }];
+        self.registers[x] = value >> 1;
+        self.registers[0xF] = value & 0x01;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XY7
+    fn op_8xy7(&mut self, x: usize, y: usize) -> usize {
+        let (result, overflow) = self.registers[y].overflowing_sub(self.registers[x]);
+        self.registers[x] = result;
+        self.registers[0xF] = if overflow { 0x00 } else { 0x01 };
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 8XYE
+    fn op_8xye(&mut self, x: usize, y: usize) -> usize {
+        let value = self.registers[if self.quirks.shift_uses_vy { y } else { x }];
+        self.registers[x] = value << 1;
+        self.registers[0xF] = value >> 7;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: 9XY0
+    fn op_9xy0(&mut self, x: usize, y: usize) -> usize {
+        if self.registers[x] != self.registers[y] {
+            self.skipped_program_counter()
+        } else {
+            self.program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: ANNN
+    fn op_annn(&mut self, nnn: usize) -> usize {
+        self.index_register = nnn;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: BNNN
+    fn op_bnnn(&self, nnn: usize) -> usize {
+        let x = if self.quirks.jump_uses_vx { nnn >> 8 } else { 0x0 };
+        nnn + (self.registers[x] as usize)
+    }
+
+    /// Opcode: BXY0
+    /// The low and high nibbles of `registers[x]` are the first and last block columns,
+    /// and those of `registers[x + 1]` are the first and last block rows
+    fn op_bxy0(&mut self, x: usize, y: usize) -> usize {
+        let color = self.registers[y] & 0x07;
+        let columns = self.registers[x];
+        let rows = self.registers[(x + 1) % REGISTER_COUNT];
+        let (left, right) = ((columns & 0x0F) as usize, (columns >> 4) as usize);
+        let (top, bottom) = ((rows & 0x0F) as usize, (rows >> 4) as usize);
+
+        for block_row in top..=bottom {
+            for row in block_row * COLOR_BLOCK_HEIGHT..(block_row + 1) * COLOR_BLOCK_HEIGHT {
+                for column in left..=right {
+                    if let Some(zone) = self.color_zones.get_mut(row).and_then(|zones| zones.get_mut(column)) {
+                        *zone = color;
+                    }
+                }
+            }
+        }
+        self.draw_flag = true;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: BXYN
+    /// The zones start at the pixel in column `registers[x]` and row `registers[x + 1]`
+    fn op_bxyn(&mut self, x: usize, y: usize, n: u8) -> usize {
+        let color = self.registers[y] & 0x07;
+        let column = (self.registers[x] as usize / COLOR_ZONE_WIDTH) % COLOR_ZONE_COLUMNS;
+        let top = self.registers[(x + 1) % REGISTER_COUNT] as usize;
+
+        for row in top..top + n as usize {
+            self.color_zones[row % SCREEN_HEIGHT][column] = color;
+        }
+        self.draw_flag = true;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: CXNN
+    fn op_cxnn(&mut self, x: usize, nn: u8) -> usize {
+        self.registers[x] = self.random.next_byte() & nn;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: DXYN
+    /// The sprite has a width of 8 pixels and a height of `n` pixels
+    /// If `n` is 0 and SUPER-CHIP instructions are available, the sprite is 16 by 16 pixels with two bytes per row
+    /// unless the screen is in low resolution and `quirks.lores_dxy0_8x16` is enabled, in which case it is 8 by 16 pixels
+    /// The sprite is drawn to each selected plane in turn, with the data for each plane following the last
+    /// The position wraps around the screen, and the parts of the sprite past the edges are clipped,
+    /// or wrapped around if `quirks.sprites_wrap` is enabled
+    /// Sets `registers[0xF]` to `0x01` if any previously set pixels are unset and `0x00` otherwise
+    fn op_dxyn(&mut self, x: usize, y: usize, n: u8) -> Result<usize, Fault> {
+        if self.megachip_mode {
+            return self.op_dxyn_megachip(x, y, n);
+        }
+
+        let lores = self.screen_width() < HIRES_SCREEN_WIDTH;
+        let (sprite_width, sprite_height) = if n == 0 && self.variant.has_superchip_instructions() {
+            if lores && self.quirks.lores_dxy0_8x16 { (8, 16) } else { (16, 16) }
+        } else {
+            (8, n as usize)
+        };
+        let bytes_per_row = sprite_width / 8;
+
+        let width = self.screen_width();
+        let height = self.screen_height();
+        let left = (self.registers[x] as usize) % width;
+        let top = (self.registers[y] as usize) % height;
+
+        self.registers[0xF] = 0x00;
+
+        let mut sprite_address = self.index_register;
+        for plane in self.selected_planes() {
+            for row_idx in 0..sprite_height {
+                let screen_y = top + row_idx;
+                if screen_y >= height && !self.quirks.sprites_wrap {
+                    break;
+                }
+                let screen_y = screen_y % height;
+
+                let mut sprite_row = 0u16;
+                for byte_idx in 0..bytes_per_row {
+                    let byte = self.read_memory(sprite_address + row_idx * bytes_per_row + byte_idx)?;
+                    sprite_row = (sprite_row << 8) | (byte as u16);
+                }
+
+                for col_idx in 0..sprite_width {
+                    if (sprite_row >> (sprite_width - 1 - col_idx)) & 0x01 == 0 {
+                        continue;
+                    }
+
+                    let screen_x = left + col_idx;
+                    if screen_x >= width && !self.quirks.sprites_wrap {
+                        break;
+                    }
+
+                    if self.display.toggle_pixel(plane, screen_x % width, screen_y) {
+                        self.registers[0xF] = 0x01;
+                    }
+                }
+            }
+
+            sprite_address += sprite_height * bytes_per_row;
+        }
+
+        self.draw_flag = true;
+        self.awaiting_vblank = self.quirks.display_wait;
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: DXYN in MEGA-CHIP mode
+    /// The sprite has the size set by 03NN and 04NN, and pixels of color 0 are transparent
+    /// Font characters selected by FX29 or FX30 are stored as 1-bit rows rather than palette indices,
+    /// so they are drawn as 1-bit sprites 8 pixels wide and `n` pixels high in white
+    /// Parts of the sprite past the edges of the screen are clipped
+    /// Sets `registers[0xF]` to `0x01` if any pixel is drawn over a pixel of the collision color and `0x00` otherwise,
+    /// where empty pixels of color 0 never collide
+    fn op_dxyn_megachip(&mut self, x: usize, y: usize, n: u8) -> Result<usize, Fault> {
+        let left = self.registers[x] as usize;
+        let top = self.registers[y] as usize;
+        let monochrome = self.font_character == Some(self.index_register);
+        let (sprite_width, sprite_height) = if monochrome {
+            (8, n as usize)
+        } else {
+            (self.sprite_width, self.sprite_height)
+        };
+
+        self.registers[0xF] = 0x00;
+
+        for row_idx in 0..sprite_height {
+            let screen_y = top + row_idx;
+            if screen_y >= MEGA_CHIP_SCREEN_HEIGHT {
+                break;
+            }
+
+            let row_byte = if monochrome { self.read_memory(self.index_register + row_idx)? } else { 0 };
+            for col_idx in 0..sprite_width {
+                let screen_x = left + col_idx;
+                if screen_x >= MEGA_CHIP_SCREEN_WIDTH {
+                    break;
+                }
+
+                let (color, argb) = if monochrome {
+                    if (row_byte >> (7 - col_idx)) & 0x01 == 0 {
+                        continue;
+                    }
+                    (MONOCHROME_COLOR_INDEX, MONOCHROME_COLOR)
+                } else {
+                    let color = self.read_memory(self.index_register + row_idx * sprite_width + col_idx)?;
+                    if color == 0 {
+                        continue;
+                    }
+                    (color, self.palette[color as usize])
+                };
+
+                let pixel = screen_y * MEGA_CHIP_SCREEN_WIDTH + screen_x;
+                let previous_color = self.megachip_indices[pixel];
+                if previous_color != 0 && previous_color == self.collision_color {
+                    self.registers[0xF] = 0x01;
+                }
+                self.megachip_indices[pixel] = color;
+                self.megachip_pixels[pixel] = self.blend_mode.blend(argb, self.megachip_pixels[pixel]);
+            }
+        }
+
+        self.draw_flag = true;
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: EX9E
+    fn op_ex9e(&self, x: usize) -> usize {
+        if self.keypad[(self.registers[x] & 0x0F) as usize] {
+            self.skipped_program_counter()
+        } else {
+            self.program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: EXA1
+    fn op_exa1(&self, x: usize) -> usize {
+        if !self.keypad[(self.registers[x] & 0x0F) as usize] {
+            self.skipped_program_counter()
+        } else {
+            self.program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: EXF2
+    fn op_exf2(&self, x: usize) -> usize {
+        if self.second_keypad[(self.registers[x] & 0x0F) as usize] {
+            self.skipped_program_counter()
+        } else {
+            self.program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: EXF5
+    fn op_exf5(&self, x: usize) -> usize {
+        if !self.second_keypad[(self.registers[x] & 0x0F) as usize] {
+            self.skipped_program_counter()
+        } else {
+            self.program_counter + INSTRUCTION_SIZE
+        }
+    }
+
+    /// Opcode: F000 NNNN
+    fn op_f000(&mut self) -> Result<usize, Fault> {
+        let high = self.fetch_byte(self.program_counter + INSTRUCTION_SIZE)?;
+        let low = self.fetch_byte(self.program_counter + INSTRUCTION_SIZE + 1)?;
+        self.index_register = u16::from_be_bytes([high, low]) as usize;
+        Ok(self.program_counter + INSTRUCTION_SIZE * 2)
+    }
+
+    /// Opcode: FN01
+    fn op_fn01(&mut self, n: u8) -> usize {
+        self.selected_planes = n & ((1 << PLANE_COUNT) - 1);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: F002
+    fn op_f002(&mut self) -> Result<usize, Fault> {
+        let mut pattern = [0; AUDIO_PATTERN_SIZE];
+        for (offset, byte) in pattern.iter_mut().enumerate() {
+            *byte = self.read_memory(self.index_register + offset)?;
+        }
+        self.audio_pattern = Some(pattern);
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: FX07
+    fn op_fx07(&mut self, x: usize) -> usize {
+        self.registers[x] = self.delay_timer;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FX0A
+    /// The program counter does not advance until the key is released
+    /// A key pressed and released since the previous cycle counts as pressed
+    fn op_fx0a(&mut self, x: usize) -> usize {
+        match self.awaited_key {
+            Some(key) if !self.keypad[key] => {
+                self.awaited_key = None;
+                self.registers[x] = key as u8;
+                self.program_counter + INSTRUCTION_SIZE
+            }
+            Some(_) => self.program_counter,
+            None => {
+                self.awaited_key = (0..KEYPAD_SIZE).find(|&key| self.keypad[key] || self.tapped_keys[key]);
+                self.program_counter
+            }
+        }
+    }
+
+    /// Opcode: FX15
+    fn op_fx15(&mut self, x: usize) -> usize {
+        self.delay_timer = self.registers[x];
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FX18
+    fn op_fx18(&mut self, x: usize) -> usize {
+        self.sound_timer = self.registers[x];
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FX1E
+    fn op_fx1e(&mut self, x: usize) -> usize {
+        self.index_register += self.registers[x] as usize;
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FX29
+    fn op_fx29(&mut self, x: usize) -> usize {
+        self.index_register = FONT_ADDRESS + ((self.registers[x] & 0x0F) as usize) * FONT_CHARACTER_SIZE;
+        self.font_character = Some(self.index_register);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FX30
+    fn op_fx30(&mut self, x: usize) -> usize {
+        self.index_register = BIG_FONT_ADDRESS + ((self.registers[x] & 0x0F) as usize) * BIG_FONT_CHARACTER_SIZE;
+        self.font_character = Some(self.index_register);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FX33
+    fn op_fx33(&mut self, x: usize) -> Result<usize, Fault> {
+        let value = self.registers[x];
+        self.write_memory(self.index_register, value / 100)?;
+        self.write_memory(self.index_register + 1, (value / 10) % 10)?;
+        self.write_memory(self.index_register + 2, value % 10)?;
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: FX3A
+    fn op_fx3a(&mut self, x: usize) -> usize {
+        self.pitch = self.registers[x];
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FX55
+    fn op_fx55(&mut self, x: usize) -> Result<usize, Fault> {
+        for offset in 0..=x {
+            self.write_memory(self.index_register + offset, self.registers[offset])?;
+        }
+        if self.quirks.load_store_increments_index {
+            self.index_register += x + 1;
+        }
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: FX65
+    fn op_fx65(&mut self, x: usize) -> Result<usize, Fault> {
+        for offset in 0..=x {
+            self.registers[offset] = self.read_memory(self.index_register + offset)?;
+        }
+        if self.quirks.load_store_increments_index {
+            self.index_register += x + 1;
+        }
+        Ok(self.program_counter + INSTRUCTION_SIZE)
+    }
+
+    /// Opcode: FX75
+    /// Only the first 8 registers are stored unless XO-CHIP instructions are available
+    fn op_fx75(&mut self, x: usize) -> usize {
+        let count = (x + 1).min(self.variant.flag_count());
+        self.flags[..count].copy_from_slice(&self.registers[..count]);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FX85
+    /// Only the first 8 registers are loaded unless XO-CHIP instructions are available
+    fn op_fx85(&mut self, x: usize) -> usize {
+        let count = (x + 1).min(self.variant.flag_count());
+        self.registers[..count].copy_from_slice(&self.flags[..count]);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FXF8
+    fn op_fxf8(&mut self, x: usize) -> usize {
+        self.output_port = Some(self.registers[x]);
+        self.program_counter + INSTRUCTION_SIZE
+    }
+
+    /// Opcode: FXFB
+    /// The program counter does not advance until a value is available
+    fn op_fxfb(&mut self, x: usize) -> usize {
+        match self.input_port.take() {
+            Some(value) => {
+                self.registers[x] = value;
+                self.program_counter + INSTRUCTION_SIZE
+            }
+            None => self.program_counter,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a processor for SUPER-CHIP 1.1 programs
+    fn superchip() -> Chip8 {
+        Chip8::with_config(Config {
+            variant: Variant::SuperChip,
+            quirks: Quirks::SUPER_CHIP_1_1,
+            font: FontSet::Standard,
+            ..Config::default()
+        })
+    }
+
+    /// Creates a processor for XO-CHIP programs
+    fn xochip() -> Chip8 {
+        Chip8::with_config(Config {
+            variant: Variant::XoChip,
+            quirks: Quirks::XO_CHIP,
+            font: FontSet::Standard,
+            ..Config::default()
+        })
+    }
+
+    /// Creates a processor for CHIP-8X programs
+    fn chip8x() -> Chip8 {
+        Chip8::with_config(Config {
+            variant: Variant::Chip8X,
+            ..Config::default()
+        })
+    }
+
+    /// Creates a processor for MEGA-CHIP programs
+    fn megachip() -> Chip8 {
+        Chip8::with_config(Config {
+            variant: Variant::MegaChip,
+            quirks: Quirks::SUPER_CHIP_1_1,
+            font: FontSet::Standard,
+            ..Config::default()
+        })
+    }
+
+    /// Gets row `y` of `plane` as a bitmap with the leftmost pixel in the most significant bit
+    fn row(chip8: &Chip8, plane: usize, y: usize) -> u128 {
+        (0..HIRES_SCREEN_WIDTH)
+            .filter(|&x| chip8.display.pixel(plane, x, y))
+            .fold(0, |bits, x| bits | 1 << (HIRES_SCREEN_WIDTH - 1 - x))
+    }
+
+    /// Gets every row of `plane` as bitmaps, as returned by `row`
+    fn plane(chip8: &Chip8, plane: usize) -> [u128; HIRES_SCREEN_HEIGHT] {
+        std::array::from_fn(|y| row(chip8, plane, y))
+    }
+
+    /// Sets row `y` of `plane` from a bitmap with the leftmost pixel in the most significant bit
+    /// Pixels outside the current resolution are ignored
+    fn set_row(chip8: &mut Chip8, plane: usize, y: usize, bits: u128) {
+        for x in 0..HIRES_SCREEN_WIDTH {
+            chip8.display.set_pixel(plane, x, y, bits & 1 << (HIRES_SCREEN_WIDTH - 1 - x) != 0);
+        }
+    }
+
+    /// Gets the rows of the low resolution screen as 64 bit bitmaps
+    fn lores_screen(chip8: &Chip8) -> [u64; SCREEN_HEIGHT] {
+        std::array::from_fn(|y| (row(chip8, 0, y) >> 64) as u64)
+    }
+
+    #[test]
+    fn test_new() {
+        let chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        assert_eq!(chip8.memory[FONT_ADDRESS..FONT_ADDRESS + FONT_SIZE], *FontSet::Standard.glyphs());
+        assert_eq!(chip8.memory[BIG_FONT_ADDRESS..BIG_FONT_ADDRESS + BIG_FONT_SIZE], BIG_FONT);
+        assert!(chip8.memory[BIG_FONT_ADDRESS + BIG_FONT_SIZE..].iter().all(|&byte| byte == 0));
+        assert_eq!(chip8.program_counter, PROGRAM_START_ADDRESS);
+    }
+
+    #[test]
+    fn test_with_font() {
+        let chip8 = Chip8::with_font(Quirks::COSMAC_VIP, FontSet::Eti660);
+        assert_eq!(chip8.memory[FONT_ADDRESS..FONT_ADDRESS + FONT_SIZE], *FontSet::Eti660.glyphs());
+    }
+
+    #[test]
+    fn test_load() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        let buffer = [1, 2, 3, 4, 5];
+
+        chip8.load(&buffer).unwrap();
+        assert_eq!(chip8.memory[0x200], 1);
+        assert_eq!(chip8.memory[0x201], 2);
+        assert_eq!(chip8.memory[0x202], 3);
+        assert_eq!(chip8.memory[0x203], 4);
+        assert_eq!(chip8.memory[0x204], 5);
+        assert_eq!(chip8.program_counter, 0x200);
+    }
+
+    #[test]
+    fn test_load_chip8x() {
+        let mut chip8 = Chip8::with_config(Config { variant: Variant::Chip8X, ..Config::default() });
+        assert_eq!(chip8.program_counter, CHIP_8X_START_ADDRESS);
+
+        chip8.load(&[1, 2, 3]).unwrap();
+        assert_eq!(chip8.memory[CHIP_8X_START_ADDRESS..CHIP_8X_START_ADDRESS + 3], [1, 2, 3]);
+        assert_eq!(chip8.memory[PROGRAM_START_ADDRESS], 0);
+        assert_eq!(chip8.program_counter, CHIP_8X_START_ADDRESS);
+    }
+
+    #[test]
+    fn test_load_at() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        let buffer = [1, 2, 3];
+
+        chip8.load_at(&buffer, ETI_660_START_ADDRESS).unwrap();
+        assert_eq!(chip8.memory[0x600..0x603], [1, 2, 3]);
+        assert_eq!(chip8.memory[0x200], 0);
+        assert_eq!(chip8.program_counter, 0x600);
+
+        let result = chip8.load_at(&buffer, MEMORY_SIZE - 2);
+        assert_eq!(result, Err(LoadError::TooLarge { size: 3, capacity: 2 }));
+        assert_eq!(chip8.memory[MEMORY_SIZE - 2..], [0, 0]);
+        assert_eq!(chip8.program_counter, 0x600);
+
+        let result = chip8.load_at(&buffer, MEMORY_SIZE + 1);
+        assert_eq!(result, Err(LoadError::TooLarge { size: 3, capacity: 0 }));
+    }
+
+    #[test]
+    fn test_xochip_memory() {
+        let mut chip8 = xochip();
+        assert_eq!(chip8.memory.len(), XO_CHIP_MEMORY_SIZE);
+
+        let buffer = vec![0xFF; XO_CHIP_MEMORY_SIZE - PROGRAM_START_ADDRESS];
+        assert_eq!(chip8.load(&buffer), Ok(()));
+        assert_eq!(chip8.memory[XO_CHIP_MEMORY_SIZE - 1], 0xFF);
+
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        assert_eq!(
+            chip8.load(&buffer),
+            Err(LoadError::TooLarge { size: buffer.len(), capacity: MEMORY_SIZE - PROGRAM_START_ADDRESS })
+        );
+    }
+
+    #[test]
+    fn test_emulate_cycle() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+        chip8.load(&[0x60, 0x2A, 0x12, 0x02]).unwrap();
+
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.registers[0x0], 0x2A);
+        assert_eq!(chip8.program_counter, 0x200 + INSTRUCTION_SIZE);
+
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Halted));
+        assert_eq!(chip8.program_counter, 0x202);
+    }
+
+    #[test]
+    fn test_emulate_cycle_waiting_for_key() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+        chip8.load(&[0xF3, 0x0A]).unwrap();
+
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::WaitingForKey));
+        assert_eq!(chip8.program_counter, 0x200);
+
+        chip8.press_key(0x5);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::WaitingForKey));
+        assert_eq!(chip8.program_counter, 0x200);
+
+        chip8.release_key(0x5);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.registers[0x3], 0x05);
+    }
+
+    #[test]
+    fn test_emulate_cycle_fault() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+        chip8.load(&[0x50, 0x01]).unwrap();
+
+        assert_eq!(
+            chip8.emulate_cycle(),
+            Err(Chip8Error::UnknownOpcode { pc: 0x200, opcode: 0x5001 })
+        );
+        assert_eq!(chip8.program_counter, 0x200);
+    }
+
+    #[test]
+    fn test_emulate_cycle_fetch_out_of_bounds() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = MEMORY_SIZE - 1;
+        chip8.memory[MEMORY_SIZE - 1] = 0x12;
+
+        assert_eq!(
+            chip8.emulate_cycle(),
+            Err(Chip8Error::MemoryOutOfBounds { pc: MEMORY_SIZE - 1, opcode: 0x1200, address: MEMORY_SIZE })
+        );
+        assert_eq!(chip8.program_counter, MEMORY_SIZE - 1);
+    }
+
+    #[test]
+    fn test_press_and_release_key() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+
+        chip8.press_key(0xA);
+        chip8.press_key(0x10);
+        assert!(chip8.keypad[0xA]);
+        assert_eq!(chip8.keypad.iter().filter(|&&pressed| pressed).count(), 1);
+
+        chip8.release_key(0xA);
+        assert_eq!(chip8.keypad, [false; KEYPAD_SIZE]);
+    }
+
+    #[test]
+    fn test_set_keys() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        let mut keys = [false; KEYPAD_SIZE];
+        keys[0x1] = true;
+        keys[0xF] = true;
+
+        chip8.set_keys(keys);
+        assert_eq!(chip8.keypad, keys);
+    }
+
+    #[test]
+    fn test_inspect_state() {
+        let mut chip8 = superchip();
+        chip8.load(&[0x6A, 0x12, 0xA3, 0x45, 0xFA, 0x15, 0x22, 0x0A]).unwrap();
+        chip8.press_key(0xB);
+        for _ in 0..4 {
+            chip8.emulate_cycle().unwrap();
+        }
+
+        assert_eq!(chip8.quirks(), Quirks::SUPER_CHIP_1_1);
+        assert_eq!(chip8.registers()[0xA], 0x12);
+        assert_eq!(chip8.index_register(), 0x345);
+        assert_eq!(chip8.program_counter(), 0x20A);
+        assert_eq!(chip8.stack(), [0x206]);
+        assert_eq!(chip8.delay_timer(), 0x12);
+        assert_eq!(chip8.sound_timer(), 0x00);
+        assert_eq!(chip8.memory()[0x200..0x202], [0x6A, 0x12]);
+        assert!(chip8.is_key_pressed(0xB));
+        assert!(!chip8.is_key_pressed(0xC));
+        assert!(!chip8.is_key_pressed(0x10));
+    }
+
+    #[test]
+    fn test_pixel() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        set_row(&mut chip8, 0, 0x1, 0x8000000000000001 << 64);
+
+        assert!(chip8.pixel(0, 1));
+        assert!(chip8.pixel(63, 1));
+        assert!(!chip8.pixel(1, 1));
+        assert!(!chip8.pixel(0, 0));
+        assert!(!chip8.pixel(64, 1));
+        assert!(!chip8.pixel(0, 32));
+    }
+
+    #[test]
+    fn test_with_display() {
+        let mut display = Bitmap::new();
+        display.set_resolution(HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+        display.set_pixel(0, 100, 50, true);
+
+        let chip8 = Chip8::with_display(Config::default(), Box::new(display));
+        assert_eq!((chip8.display().width(), chip8.display().height()), (SCREEN_WIDTH, SCREEN_HEIGHT));
+        assert!(!chip8.display().pixel(0, 100, 50));
+
+        let chip8 = Chip8::with_config(Config {
+            variant: Variant::HiresChip8,
+            ..Config::default()
+        });
+        assert_eq!(chip8.screen_height(), TWO_PAGE_SCREEN_HEIGHT);
+    }
+
+    #[test]
+    fn test_pixels() {
+        let mut chip8 = xochip();
+        chip8.display.set_pixel(0, 1, 0, true);
+        chip8.display.set_pixel(0, 0, 1, true);
+        chip8.display.set_pixel(1, 0, 1, true);
+
+        let pixels: Vec<u8> = chip8.pixels().collect();
+        assert_eq!(pixels.len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+        assert_eq!(pixels[..2], [0b00, 0b01]);
+        assert_eq!(pixels[SCREEN_WIDTH..SCREEN_WIDTH + 2], [0b11, 0b00]);
+    }
+
+    #[test]
+    fn test_to_rgba() {
+        let colors = [[0, 0, 0, 0xFF], [0xFF, 0, 0, 0xFF], [0, 0xFF, 0, 0xFF], [0, 0, 0xFF, 0xFF]];
+        let mut chip8 = xochip();
+        chip8.display.set_pixel(0, 1, 0, true);
+        chip8.display.set_pixel(1, 2, 0, true);
+
+        let rgba = chip8.to_rgba(&colors);
+        assert_eq!(rgba.len(), SCREEN_WIDTH * SCREEN_HEIGHT * 4);
+        assert_eq!(rgba[..12], [0, 0, 0, 0xFF, 0xFF, 0, 0, 0xFF, 0, 0xFF, 0, 0xFF]);
+
+        let mut chip8 = megachip();
+        chip8.op_0011();
+        chip8.megachip_pixels[0] = 0xFF_12_34_56;
+        chip8.screen_alpha = 0x80;
+        let rgba = chip8.to_rgba(&colors);
+        assert_eq!(rgba.len(), MEGA_CHIP_SCREEN_WIDTH * MEGA_CHIP_SCREEN_HEIGHT * 4);
+        assert_eq!(rgba[..8], [0x12, 0x34, 0x56, 0x80, 0, 0, 0, 0x80]);
+    }
+
+    #[test]
+    fn test_save_state() {
+        let mut chip8 = xochip();
+        chip8.load(&[0x00, 0xFF, 0x60, 0x2A, 0x22, 0x08, 0x00, 0x00, 0xF2, 0x01, 0xD0, 0x01, 0x00, 0xEE]).unwrap();
+        chip8.memory[0x300] = 0xFF;
+        chip8.index_register = 0x300;
+        chip8.delay_timer = 0x10;
+        chip8.flags[0xF] = 0x99;
+        chip8.audio_pattern = Some([0xAA; AUDIO_PATTERN_SIZE]);
+        chip8.press_key(0x7);
+        for _ in 0..5 {
+            chip8.emulate_cycle().unwrap();
+        }
+        chip8.press_key(0x3);
+        chip8.release_key(0x3);
+
+        let state = chip8.save_state();
+        let mut restored = Chip8::new(Quirks::COSMAC_VIP);
+        restored.load_state(&state).unwrap();
+        assert_eq!(restored.variant, Variant::XoChip);
+        assert_eq!(restored.quirks, Quirks::XO_CHIP);
+        assert_eq!(restored.registers, chip8.registers);
+        assert_eq!(restored.program_counter, 0x20C);
+        assert_eq!(restored.stack[..restored.stack_pointer], [0x204]);
+        assert_eq!(restored.memory, chip8.memory);
+        assert_eq!(restored.keypad, chip8.keypad);
+        assert_eq!(restored.tapped_keys, chip8.tapped_keys);
+        assert!(restored.tapped_keys[0x3]);
+        assert_eq!(restored.flags, chip8.flags);
+        assert_eq!(restored.audio_pattern, chip8.audio_pattern);
+        assert_eq!(restored.screen_width(), HIRES_SCREEN_WIDTH);
+        assert_eq!(plane(&restored, 0), plane(&chip8, 0));
+        assert_eq!(plane(&restored, 1), plane(&chip8, 1));
+        assert!(restored.take_draw_flag());
+        assert_eq!(restored.save_state(), state);
+
+        chip8.emulate_cycle().unwrap();
+        restored.emulate_cycle().unwrap();
+        assert_eq!(restored.save_state(), chip8.save_state());
+    }
+
+    #[test]
+    fn test_save_state_megachip() {
+        let mut chip8 = megachip();
+        chip8.op_0011();
+        chip8.megachip_indices[5] = 0x01;
+        chip8.megachip_pixels[5] = 0xFF12_3456;
+        chip8.palette[1] = 0xFF12_3456;
+        chip8.blend_mode = BlendMode::Multiply;
+        chip8.digitized_sound = Some(DigitizedSound {
+            sample_rate: 8000,
+            samples: vec![0x80, 0xFF],
+            looping: true,
+        });
+        chip8.memory[MEGA_CHIP_MEMORY_SIZE - 1] = 0x42;
+
+        // Memory that is all zero is left out of the state
+        let state = chip8.save_state();
+        assert!(state.len() < MEGA_CHIP_MEMORY_SIZE / 16);
+
+        let mut restored = Chip8::new(Quirks::COSMAC_VIP);
+        restored.load_state(&state).unwrap();
+        assert_eq!(restored.memory, chip8.memory);
+        assert!(restored.is_megachip_mode());
+        assert_eq!(restored.megachip_indices, chip8.megachip_indices);
+        assert_eq!(restored.megachip_pixels, chip8.megachip_pixels);
+        assert_eq!(restored.blend_mode, BlendMode::Multiply);
+        assert_eq!(restored.digitized_sound, chip8.digitized_sound);
+        assert!(restored.take_sound_changed());
+    }
+
+    #[test]
+    fn test_load_state_errors() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x0] = 0x42;
+        let mut state = chip8.save_state();
+
+        let mut other = superchip();
+        assert_eq!(other.load_state(&state[..state.len() - 1]), Err(StateError::ChecksumMismatch));
+        assert_eq!(other.load_state(b"not a state"), Err(StateError::NotAState));
+
+        let name = state.windows(5).position(|bytes| bytes == b"chip8").unwrap();
+        state[name..name + 5].copy_from_slice(b"eti66");
+        let checksum_start = state.len() - 4;
+        let checksum = crate::state::crc32(&state[..checksum_start]);
+        state[checksum_start..].copy_from_slice(&checksum.to_be_bytes());
+        assert_eq!(other.load_state(&state), Err(StateError::Malformed));
+        assert_eq!(other.variant, Variant::SuperChip);
+        assert_eq!(other.registers[0x0], 0x00);
+    }
+
+    #[test]
+    fn test_take_draw_flag() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        assert!(!chip8.take_draw_flag());
+
+        chip8.op_00e0();
+        assert!(chip8.take_draw_flag());
+        assert!(!chip8.take_draw_flag());
+    }
+
+    #[test]
+    fn test_run_frame() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.delay_timer = 0x02;
+        chip8.load(&[0x70, 0x01, 0x70, 0x01, 0x70, 0x01, 0x12, 0x06]).unwrap();
+
+        assert_eq!(chip8.run_frame(2), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.registers[0x0], 0x02);
+        assert_eq!(chip8.delay_timer, 0x01);
+
+        assert_eq!(chip8.run_frame(10), Ok(CycleOutcome::Halted));
+        assert_eq!(chip8.registers[0x0], 0x03);
+        assert_eq!(chip8.program_counter, 0x206);
+        assert_eq!(chip8.delay_timer, 0x00);
+    }
+
+    #[test]
+    fn test_run_frame_display_wait_quirk() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.load(&[0xD0, 0x01, 0xD0, 0x01, 0x12, 0x04]).unwrap();
+
+        assert_eq!(chip8.run_frame(10), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, 0x202);
+
+        let mut chip8 = Chip8::new(Quirks::MODERN);
+        chip8.load(&[0xD0, 0x01, 0xD0, 0x01, 0x12, 0x04]).unwrap();
+
+        assert_eq!(chip8.run_frame(10), Ok(CycleOutcome::Halted));
+        assert_eq!(chip8.program_counter, 0x204);
+    }
+
+    #[test]
+    fn test_run_cycles() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.delay_timer = 0x02;
+        chip8.load(&[0x70, 0x01, 0x70, 0x01, 0x12, 0x04]).unwrap();
+
+        assert_eq!(chip8.run_cycles(1), Ok((CycleOutcome::Executed, 1)));
+        assert_eq!(chip8.registers[0x0], 0x01);
+        assert_eq!(chip8.delay_timer, 0x02);
+
+        assert_eq!(chip8.run_cycles(10), Ok((CycleOutcome::Halted, 2)));
+        assert_eq!(chip8.program_counter, 0x204);
+    }
+
+    /// A program that accesses 0x300 to 0x304 through `index_register` with FX33, FX65, DXYN and FX55
+    const WATCHED_PROGRAM: [u8; 14] = [
+        0xA3, 0x00, 0x6A, 0x7B, 0xFA, 0x33, 0xF2, 0x65, 0xD0, 0x15, 0xF1, 0x55, 0x12, 0x0C,
+    ];
+
+    #[test]
+    fn test_watchpoints() {
+        let mut chip8 = Chip8::new(Quirks::MODERN);
+        chip8.load(&WATCHED_PROGRAM).unwrap();
+        chip8.add_watchpoint(Watchpoint { addresses: 0x301..=0x301, reads: true, writes: true, stops: false });
+        // Fetching instructions is not a read
+        chip8.add_watchpoint(Watchpoint { addresses: 0x200..=0x20D, reads: true, writes: true, stops: false });
+
+        assert_eq!(chip8.run_cycles(10), Ok((CycleOutcome::Halted, 7)));
+        let hit = |pc, access| WatchHit { pc, address: 0x301, access, value: 0x02 };
+        assert_eq!(
+            chip8.take_watch_hits(),
+            [hit(0x204, Access::Write), hit(0x206, Access::Read), hit(0x208, Access::Read), hit(0x20A, Access::Write)]
+        );
+        assert_eq!(chip8.take_watch_hits(), []);
+
+        // Watchpoints survive loading a state
+        chip8.load_state(&chip8.save_state()).unwrap();
+        assert_eq!(chip8.watchpoints().len(), 2);
+        assert_eq!(chip8.remove_watchpoint(1).map(|watchpoint| watchpoint.addresses), Some(0x200..=0x20D));
+        assert_eq!(chip8.remove_watchpoint(1), None);
+        chip8.clear_watchpoints();
+        assert!(chip8.watchpoints().is_empty());
+    }
+
+    #[test]
+    fn test_watchpoint_stops() {
+        let mut chip8 = Chip8::new(Quirks::MODERN);
+        chip8.load(&WATCHED_PROGRAM).unwrap();
+        chip8.add_watchpoint(Watchpoint { addresses: 0x302..=0x310, reads: false, writes: true, stops: true });
+
+        // The instruction completes before execution stops
+        assert_eq!(chip8.run_cycles(10), Ok((CycleOutcome::Watchpoint, 3)));
+        assert_eq!(chip8.program_counter, 0x206);
+        assert_eq!(
+            chip8.take_watch_hits(),
+            [WatchHit { pc: 0x204, address: 0x302, access: Access::Write, value: 0x03 }]
+        );
+
+        assert_eq!(chip8.run_frame(10), Ok(CycleOutcome::Halted));
+        assert_eq!(chip8.take_watch_hits(), []);
+    }
+
+    #[test]
+    fn test_watchpoint_stops_before_fault() {
+        // FX55 writes the watched byte at the end of memory and then faults on the byte past it
+        let mut chip8 = Chip8::new(Quirks::MODERN);
+        chip8.load(&[0xAF, 0xFE, 0xF3, 0x55, 0x60, 0x01]).unwrap();
+        chip8.add_watchpoint(Watchpoint { addresses: 0xFFF..=0xFFF, reads: false, writes: true, stops: true });
+
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(
+            chip8.emulate_cycle(),
+            Err(Chip8Error::MemoryOutOfBounds { pc: 0x202, opcode: 0xF355, address: MEMORY_SIZE })
+        );
+        assert_eq!(chip8.take_watch_hits().len(), 1);
+
+        chip8.program_counter = 0x204;
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.registers[0x0], 0x01);
+    }
+
+    #[test]
+    fn test_watchpoint_digitized_sound() {
+        let mut chip8 = megachip();
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x309].copy_from_slice(&[0x1F, 0x40, 0x00, 0x00, 0x03, 0x00, 0x80, 0xFF, 0x00]);
+        chip8.add_watchpoint(Watchpoint { addresses: 0x307..=0x400, reads: true, writes: false, stops: false });
+
+        chip8.op_060n(0x1).unwrap();
+        let addresses: Vec<(usize, u8)> = chip8.take_watch_hits().iter().map(|hit| (hit.address, hit.value)).collect();
+        assert_eq!(addresses, [(0x307, 0xFF), (0x308, 0x00)]);
+    }
+
+    #[test]
+    fn test_describe_instruction() {
+        let chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        assert_eq!(chip8.describe_instruction(0x200, 0x8014).map(|(pattern, _)| pattern), Some("8XY4"));
+        assert_eq!(chip8.describe_instruction(0x200, 0x00FF), None);
+
+        let chip8 = Chip8::with_config(Config { variant: Variant::SuperChip, ..Config::default() });
+        assert_eq!(chip8.describe_instruction(0x200, 0x00FF).map(|(pattern, _)| pattern), Some("00FF"));
+
+        let chip8 = Chip8::with_config(Config { variant: Variant::HiresChip8, ..Config::default() });
+        assert_eq!(chip8.describe_instruction(0x200, 0x1260).map(|(pattern, _)| pattern), Some("1260"));
+        assert_eq!(chip8.describe_instruction(0x202, 0x1260).map(|(pattern, _)| pattern), Some("1NNN"));
+    }
+
+    #[test]
+    fn test_describe_instruction_quirks() {
+        let explain = |quirks, opcode| Chip8::new(quirks).describe_instruction(0x200, opcode).unwrap().1;
+        assert_eq!(explain(Quirks::COSMAC_VIP, 0xB234), "Sets the program counter to nnn + registers[0x0]");
+        assert_eq!(
+            explain(Quirks::SUPER_CHIP_1_1, 0xB234),
+            "Sets the program counter to nnn + registers[x], where x is the highest nibble of nnn"
+        );
+        assert_eq!(
+            explain(Quirks::COSMAC_VIP, 0x8016),
+            "Stores registers[y] shifted right one bit in registers[x], setting registers[0xF] to the bit shifted out"
+        );
+        assert_eq!(
+            explain(Quirks::MODERN, 0x801E),
+            "Shifts registers[x] left one bit, setting registers[0xF] to the bit shifted out"
+        );
+        assert!(explain(Quirks::COSMAC_VIP, 0x8011).ends_with("resetting registers[0xF] to 0x00"));
+        assert!(!explain(Quirks::MODERN, 0x8011).contains("registers[0xF]"));
+        assert!(explain(Quirks::MODERN, 0x8014).ends_with("registers[0xF] to 0x01 on overflow and 0x00 otherwise"));
+        assert!(explain(Quirks::MODERN, 0x8015).ends_with("registers[0xF] to 0x00 on a borrow and 0x01 otherwise"));
+    }
+
+    #[test]
+    fn test_describe_every_instruction() {
+        for name in Variant::NAMES {
+            let variant = Variant::from_name(name).unwrap();
+            let mut chip8 = Chip8::with_config(Config { variant, ..Config::default() });
+            chip8.two_page = true;
+            for opcode in 0..=u16::MAX {
+                let Some(instruction) = chip8.decode(PROGRAM_START_ADDRESS, opcode) else {
+                    continue;
+                };
+                assert!(!instruction.pattern().is_empty(), "{variant:?} {opcode:04X}");
+                for preset in Quirks::PRESET_NAMES {
+                    let quirks = Quirks::preset(preset).unwrap();
+                    for megachip_mode in [false, true] {
+                        let explanation = instruction.explain(&quirks, megachip_mode);
+                        assert!(!explanation.is_empty(), "{variant:?} {preset} {megachip_mode} {opcode:04X}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_describe_instruction_matches_decoding() {
+        for variant in [Variant::Chip8, Variant::Chip8X, Variant::SuperChip] {
+            for opcode in 0..=u16::MAX {
+                let mut chip8 = Chip8::with_config(Config { variant, ..Config::default() });
+                chip8.load_at(&opcode.to_be_bytes(), 0x300).unwrap();
+                let unknown = matches!(chip8.emulate_cycle(), Err(Chip8Error::UnknownOpcode { .. }));
+                assert_eq!(chip8.describe_instruction(0x300, opcode).is_none(), unknown, "{variant:?} {opcode:04X}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_tick_timers() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.delay_timer = 0x02;
+        chip8.sound_timer = 0x01;
+        assert!(chip8.is_sound_playing());
+
+        chip8.tick_timers();
+        assert_eq!(chip8.delay_timer, 0x01);
+        assert_eq!(chip8.sound_timer, 0x00);
+        assert!(!chip8.is_sound_playing());
+
+        chip8.tick_timers();
+        assert_eq!(chip8.delay_timer, 0x00);
+        assert_eq!(chip8.sound_timer, 0x00);
+    }
+
+    #[test]
+    fn test_emulate_cycle_stack_errors() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.load(&[0x00, 0xEE]).unwrap();
+
+        assert_eq!(
+            chip8.emulate_cycle(),
+            Err(Chip8Error::StackUnderflow { pc: 0x200, opcode: 0x00EE })
+        );
+
+        chip8.load(&[0x22, 0x00]).unwrap();
+        for _ in 0..Variant::Chip8.stack_depth() {
+            assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        }
+        assert_eq!(
+            chip8.emulate_cycle(),
+            Err(Chip8Error::StackOverflow { pc: 0x200, opcode: 0x2200 })
+        );
+    }
+
+    #[test]
+    fn test_emulate_cycle_superchip_instructions() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.load(&[0x00, 0xFF]).unwrap();
+        assert_eq!(
+            chip8.emulate_cycle(),
+            Err(Chip8Error::UnknownOpcode { pc: 0x200, opcode: 0x00FF })
+        );
+
+        let mut chip8 = superchip();
+        chip8.load(&[0x00, 0xFF, 0x00, 0xFD]).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.screen_width(), HIRES_SCREEN_WIDTH);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Halted));
+        assert_eq!(chip8.program_counter, 0x202);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Halted));
+    }
+
+    #[test]
+    fn test_superchip_stack_depth() {
+        let mut chip8 = superchip();
+        chip8.load(&[0x22, 0x00]).unwrap();
+        for _ in 0..Variant::SuperChip.stack_depth() {
+            assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        }
+        assert_eq!(
+            chip8.emulate_cycle(),
+            Err(Chip8Error::StackOverflow { pc: 0x200, opcode: 0x2200 })
+        );
+    }
+
+    #[test]
+    fn test_two_page_start() {
+        let hires = || Chip8::with_config(Config { variant: Variant::HiresChip8, ..Config::default() });
+        let mut chip8 = hires();
+        chip8.load(&[0x12, 0x60]).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, TWO_PAGE_PROGRAM_ADDRESS);
+        assert_eq!((chip8.screen_width(), chip8.screen_height()), (SCREEN_WIDTH, TWO_PAGE_SCREEN_HEIGHT));
+
+        // A plain CHIP-8 program may start by jumping to 0x260
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.load(&[0x12, 0x60]).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, 0x260);
+        assert_eq!((chip8.screen_width(), chip8.screen_height()), (SCREEN_WIDTH, SCREEN_HEIGHT));
+
+        let mut chip8 = hires();
+        chip8.load_at(&[0x12, 0x60], 0x300).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, 0x260);
+
+        let mut chip8 = superchip();
+        chip8.load(&[0x12, 0x60]).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, 0x260);
+        assert!(!chip8.two_page);
+
+        assert_eq!(hires().screen_height(), TWO_PAGE_SCREEN_HEIGHT);
+    }
+
+    #[test]
+    fn test_op_0010_and_0011() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+        assert_eq!(chip8.memory.len(), MEGA_CHIP_MEMORY_SIZE);
+
+        let result = chip8.op_0011();
+        assert!(chip8.is_megachip_mode());
+        assert_eq!((chip8.screen_width(), chip8.screen_height()), (MEGA_CHIP_SCREEN_WIDTH, MEGA_CHIP_SCREEN_HEIGHT));
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.megachip_indices[MEGA_CHIP_SCREEN_WIDTH * 100 + 200] = 0x01;
+        assert_eq!(chip8.pixel_planes(200, 100), 0b01);
+        chip8.op_00e0();
+        assert_eq!(chip8.pixel_planes(200, 100), 0b00);
+
+        set_row(&mut chip8, 0, 0x0, u128::MAX);
+        let result = chip8.op_0010();
+        assert!(!chip8.is_megachip_mode());
+        assert_eq!((chip8.screen_width(), chip8.screen_height()), (SCREEN_WIDTH, SCREEN_HEIGHT));
+        assert_eq!(plane(&chip8, 0)[0x0], 0);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_01nn() {
+        let mut chip8 = megachip();
+        chip8.load(&[0x01, 0x12, 0x34, 0x56]).unwrap();
+
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.index_register, 0x123456);
+        assert_eq!(chip8.program_counter, 0x200 + INSTRUCTION_SIZE * 2);
+
+        chip8.load(&[0x30, 0x00, 0x01, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, 0x206);
+    }
+
+    #[test]
+    fn test_op_02nn() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x308].copy_from_slice(&[0xFF, 0x11, 0x22, 0x33, 0x80, 0x44, 0x55, 0x66]);
+
+        let result = chip8.op_02nn(0x02);
+        assert_eq!(chip8.palette[0..4], [0x00000000, 0xFF112233, 0x80445566, 0x00000000]);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        chip8.index_register = MEGA_CHIP_MEMORY_SIZE - 4;
+        assert_eq!(chip8.op_02nn(0x02), Err(Fault::MemoryOutOfBounds(MEGA_CHIP_MEMORY_SIZE)));
+    }
+
+    #[test]
+    fn test_op_03nn_and_04nn() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+
+        assert_eq!(chip8.op_03nn(0x10), 0x200 + INSTRUCTION_SIZE);
+        assert_eq!(chip8.op_04nn(0x20), 0x200 + INSTRUCTION_SIZE);
+        assert_eq!((chip8.sprite_width, chip8.sprite_height), (0x10, 0x20));
+
+        chip8.op_03nn(0x00);
+        chip8.op_04nn(0x00);
+        assert_eq!((chip8.sprite_width, chip8.sprite_height), (MAX_SPRITE_SIZE, MAX_SPRITE_SIZE));
+    }
+
+    #[test]
+    fn test_op_05nn() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+        assert_eq!(chip8.screen_alpha(), 0xFF);
+
+        let result = chip8.op_05nn(0x40);
+        assert_eq!(chip8.screen_alpha(), 0x40);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_060n_and_07nn() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x309].copy_from_slice(&[0x1F, 0x40, 0x00, 0x00, 0x03, 0x00, 0x80, 0xFF, 0x00]);
+
+        let result = chip8.op_060n(0x1);
+        assert_eq!(
+            chip8.digitized_sound(),
+            Some(&DigitizedSound { sample_rate: 8000, samples: vec![0x80, 0xFF, 0x00], looping: false })
+        );
+        assert!(chip8.take_sound_changed());
+        assert!(!chip8.take_sound_changed());
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        chip8.op_060n(0x0).unwrap();
+        assert!(chip8.digitized_sound().unwrap().looping);
+
+        let result = chip8.op_07nn();
+        assert_eq!(chip8.digitized_sound(), None);
+        assert!(chip8.take_sound_changed());
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.memory[0x302..0x305].copy_from_slice(&[0xFF, 0xFF, 0xFF]);
+        assert_eq!(chip8.op_060n(0x0), Err(Fault::MemoryOutOfBounds(MEGA_CHIP_MEMORY_SIZE)));
+    }
+
+    #[test]
+    fn test_op_080n() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_080n(0x3);
+        assert_eq!(chip8.blend_mode, BlendMode::Additive);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        assert_eq!(chip8.op_080n(0x5), Err(Fault::UnknownOpcode));
+        assert_eq!(chip8.blend_mode, BlendMode::Additive);
+    }
+
+    #[test]
+    fn test_op_09nn() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_09nn(0x07);
+        assert_eq!(chip8.collision_color, 0x07);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_0230() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.load(&[0x02, 0x30]).unwrap();
+        assert_eq!(
+            chip8.emulate_cycle(),
+            Err(Chip8Error::UnknownOpcode { pc: 0x200, opcode: 0x0230 })
+        );
+
+        chip8.two_page = true;
+        chip8.set_lores();
+        set_row(&mut chip8, 0, TWO_PAGE_SCREEN_HEIGHT - 1, u128::MAX);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(plane(&chip8, 0), [0; HIRES_SCREEN_HEIGHT]);
+        assert!(chip8.draw_flag);
+        assert_eq!(chip8.program_counter, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_02a0() {
+        let mut chip8 = chip8x();
+        chip8.program_counter = 0x200;
+        assert_eq!(chip8.background_color(), 0);
+
+        let result = chip8.op_02a0();
+        assert_eq!(chip8.background_color(), 1);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.op_02a0();
+        chip8.op_02a0();
+        chip8.op_02a0();
+        assert_eq!(chip8.background_color(), 0);
+    }
+
+    #[test]
+    fn test_op_00bn() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+        chip8.op_0011();
+        let pixel = |x: usize, y: usize| y * MEGA_CHIP_SCREEN_WIDTH + x;
+        chip8.megachip_indices[pixel(10, 5)] = 0x01;
+        chip8.megachip_pixels[pixel(10, 5)] = 0xFF12_3456;
+        chip8.draw_flag = false;
+
+        let result = chip8.op_00bn(0x3);
+        assert_eq!(chip8.megachip_indices[pixel(10, 2)], 0x01);
+        assert_eq!(chip8.megachip_pixels[pixel(10, 2)], 0xFF12_3456);
+        assert_eq!(chip8.megachip_pixels.iter().filter(|&&color| color != 0).count(), 1);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.load(&[0x00, 0xB2]).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.megachip_indices[pixel(10, 0)], 0x01);
+    }
+
+    #[test]
+    fn test_megachip_scroll() {
+        let mut chip8 = megachip();
+        chip8.op_0011();
+        let pixel = |x: usize, y: usize| y * MEGA_CHIP_SCREEN_WIDTH + x;
+        chip8.megachip_indices[pixel(10, 5)] = 0x01;
+        chip8.megachip_pixels[pixel(10, 5)] = 0xFF12_3456;
+        set_row(&mut chip8, 0, 0x0, 0x1 << 127);
+
+        chip8.op_00cn(0x2);
+        chip8.op_00fb();
+        assert_eq!(chip8.megachip_pixels[pixel(14, 7)], 0xFF12_3456);
+        chip8.op_00fc();
+        chip8.op_00fc();
+        assert_eq!(chip8.megachip_indices[pixel(6, 7)], 0x01);
+        assert_eq!(chip8.megachip_pixels[pixel(6, 7)], 0xFF12_3456);
+        assert_eq!(chip8.megachip_pixels.iter().filter(|&&color| color != 0).count(), 1);
+        assert_eq!(row(&chip8, 0, 0x0), 0x1 << 127);
+
+        chip8.op_0010();
+        set_row(&mut chip8, 0, 0x0, 0x1 << 127);
+        chip8.op_00cn(0x2);
+        assert_eq!(row(&chip8, 0, 0x2), 0x1 << 127);
+    }
+
+    #[test]
+    fn test_op_00cn() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+        set_row(&mut chip8, 0, 0x0, 0x1 << 127);
+        set_row(&mut chip8, 0, 0x1, 0x3 << 126);
+        set_row(&mut chip8, 0, SCREEN_HEIGHT - 1, u128::MAX << 64);
+
+        let result = chip8.op_00cn(0x2);
+        assert_eq!(plane(&chip8, 0)[0x0..0x4], [0, 0, 0x1 << 127, 0x3 << 126]);
+        assert_eq!(plane(&chip8, 0)[SCREEN_HEIGHT..], [0; HIRES_SCREEN_HEIGHT - SCREEN_HEIGHT]);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.display.set_resolution(HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+        set_row(&mut chip8, 0, 0x2, 0x1 << 127);
+        set_row(&mut chip8, 0, HIRES_SCREEN_HEIGHT - 1, u128::MAX);
+        chip8.op_00cn(0xF);
+        assert_eq!(plane(&chip8, 0)[0x11], 0x1 << 127);
+        assert_eq!(plane(&chip8, 0)[HIRES_SCREEN_HEIGHT - 1], 0);
+    }
+
+    #[test]
+    fn test_op_00dn() {
+        let mut chip8 = xochip();
+        chip8.program_counter = 0x200;
+        set_row(&mut chip8, 0, 0x0, u128::MAX);
+        set_row(&mut chip8, 0, 0x2, 0x1 << 127);
+        set_row(&mut chip8, 0, SCREEN_HEIGHT - 1, 0x3 << 126);
+        set_row(&mut chip8, 1, 0x2, 0x1 << 127);
+
+        let result = chip8.op_00dn(0x2);
+        assert_eq!(plane(&chip8, 0)[0x0], 0x1 << 127);
+        assert_eq!(plane(&chip8, 0)[SCREEN_HEIGHT - 3..SCREEN_HEIGHT], [0x3 << 126, 0, 0]);
+        assert_eq!(plane(&chip8, 1)[0x2], 0x1 << 127);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_00e0() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        for y in 0..SCREEN_HEIGHT {
+            set_row(&mut chip8, 0, y, u128::MAX);
+        }
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_00e0();
+        assert_eq!(plane(&chip8, 0), [0; HIRES_SCREEN_HEIGHT]);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_00e0_selected_planes() {
+        let mut chip8 = xochip();
+        for (plane, y) in (0..PLANE_COUNT).flat_map(|plane| (0..SCREEN_HEIGHT).map(move |y| (plane, y))) {
+            set_row(&mut chip8, plane, y, u128::MAX);
+        }
+
+        chip8.selected_planes = 0b10;
+        chip8.op_00e0();
+        assert_eq!(lores_screen(&chip8), [u64::MAX; SCREEN_HEIGHT]);
+        assert_eq!(plane(&chip8, 1), [0; HIRES_SCREEN_HEIGHT]);
+
+        chip8.selected_planes = 0b00;
+        chip8.op_00e0();
+        assert_eq!(lores_screen(&chip8), [u64::MAX; SCREEN_HEIGHT]);
+    }
+
+    #[test]
+    fn test_op_00ee() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.stack_pointer = 0x01;
+        chip8.stack[0] = 0x300;
+        
+        let result = chip8.op_00ee();
+        assert_eq!(result, Ok(0x300 + INSTRUCTION_SIZE));
+        assert_eq!(chip8.stack_pointer, 0);
+    }
+
+    #[test]
+    fn test_op_00ee_underflow() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.stack_pointer = 0x00;
+
+        let result = chip8.op_00ee();
+        assert_eq!(result, Err(Fault::StackUnderflow));
+        assert_eq!(chip8.stack_pointer, 0);
+    }
+
+    #[test]
+    fn test_op_00fb() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+        set_row(&mut chip8, 0, 0x0, 0xF0 << 120);
+        set_row(&mut chip8, 0, 0x1, 0x1 << 64);
+
+        let result = chip8.op_00fb();
+        assert_eq!(lores_screen(&chip8)[0x0], 0x0F << 56);
+        assert_eq!(lores_screen(&chip8)[0x1], 0);
+        assert_eq!(plane(&chip8, 0)[0x1], 0);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.display.set_resolution(HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+        set_row(&mut chip8, 0, 0x2, 0x1 << 64);
+        chip8.op_00fb();
+        assert_eq!(plane(&chip8, 0)[0x2], 0x1 << 60);
+    }
+
+    #[test]
+    fn test_op_00fc() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+        set_row(&mut chip8, 0, 0x0, 0xF0 << 120);
+        set_row(&mut chip8, 0, 0x1, 0x1 << 64);
+
+        let result = chip8.op_00fc();
+        assert_eq!(plane(&chip8, 0)[0x0], 0);
+        assert_eq!(lores_screen(&chip8)[0x1], 0x1 << 4);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_00fd() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_00fd();
+        assert_eq!(result, 0x200);
+    }
+
+    #[test]
+    fn test_op_00fe() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+        chip8.display.set_resolution(HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+        set_row(&mut chip8, 0, 0x3F, 0x1);
+
+        let result = chip8.op_00fe();
+        assert_eq!(chip8.screen_width(), SCREEN_WIDTH);
+        assert_eq!((chip8.screen_width(), chip8.screen_height()), (SCREEN_WIDTH, SCREEN_HEIGHT));
+        assert_eq!(plane(&chip8, 0), [0; HIRES_SCREEN_HEIGHT]);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_00ff() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+        set_row(&mut chip8, 0, 0x0, u128::MAX);
+
+        let result = chip8.op_00ff();
+        assert_eq!(chip8.screen_width(), HIRES_SCREEN_WIDTH);
+        assert_eq!((chip8.screen_width(), chip8.screen_height()), (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT));
+        assert_eq!(plane(&chip8, 0), [0; HIRES_SCREEN_HEIGHT]);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_1nnn() {
+        let chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        assert_eq!(chip8.op_1nnn(0x400), 0x400);
+    }
+
+    #[test]
+    fn test_op_2nnn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+        chip8.stack_pointer = 0x00;
+
+        let result = chip8.op_2nnn(0x600);
+        assert_eq!(result, Ok(0x600));
+        assert_eq!(chip8.stack[0], 0x200);
+        assert_eq!(chip8.stack_pointer, 1);
+    }
+
+    #[test]
+    fn test_op_2nnn_overflow() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.stack_pointer = MAX_STACK_SIZE;
+
+        let result = chip8.op_2nnn(0x600);
+        assert_eq!(result, Err(Fault::StackOverflow));
+        assert_eq!(chip8.stack_pointer, MAX_STACK_SIZE);
+    }
+
+    #[test]
+    fn test_op_3xnn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x42;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_3xnn(0x1, 0x42);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
+
+        let result = chip8.op_3xnn(0x1, 0x43);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_4xnn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x42;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_4xnn(0x1, 0x43);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
+
+        let result = chip8.op_4xnn(0x1, 0x42);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_5xy0() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x50;
+        chip8.registers[0x2] = 0x50;
+        chip8.registers[0x3] = 0x51;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_5xy0(0x1, 0x2);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
+
+        let result = chip8.op_5xy0(0x1, 0x3);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_5xy1() {
+        let mut chip8 = chip8x();
+        chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x35;
+        chip8.registers[0x2] = 0x14;
+
+        let result = chip8.op_5xy1(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x41);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.registers[0x1] = 0xFF;
+        chip8.registers[0x2] = 0x11;
+        chip8.op_5xy1(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x00);
+    }
+
+    #[test]
+    fn test_op_5xy2() {
+        let mut chip8 = xochip();
+        chip8.program_counter = 0x200;
+        chip8.index_register = 0x300;
+        chip8.registers[0x2..0x5].copy_from_slice(&[0x01, 0x02, 0x03]);
+
+        let result = chip8.op_5xy2(0x2, 0x4);
+        assert_eq!(chip8.memory[0x300..0x304], [0x01, 0x02, 0x03, 0x00]);
+        assert_eq!(chip8.index_register, 0x300);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        chip8.op_5xy2(0x4, 0x2).unwrap();
+        assert_eq!(chip8.memory[0x300..0x304], [0x03, 0x02, 0x01, 0x00]);
+
+        chip8.index_register = XO_CHIP_MEMORY_SIZE - 1;
+        let result = chip8.op_5xy2(0x2, 0x4);
+        assert_eq!(result, Err(Fault::MemoryOutOfBounds(XO_CHIP_MEMORY_SIZE)));
+    }
+
+    #[test]
+    fn test_op_5xy3() {
+        let mut chip8 = xochip();
+        chip8.program_counter = 0x200;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x303].copy_from_slice(&[0x01, 0x02, 0x03]);
+
+        let result = chip8.op_5xy3(0x2, 0x4);
+        assert_eq!(chip8.registers[0x1..0x6], [0x00, 0x01, 0x02, 0x03, 0x00]);
+        assert_eq!(chip8.index_register, 0x300);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        chip8.op_5xy3(0x4, 0x2).unwrap();
+        assert_eq!(chip8.registers[0x1..0x6], [0x00, 0x03, 0x02, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_op_6xnn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_6xnn(0x1, 0x99);
+        assert_eq!(chip8.registers[0x1], 0x99);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_7xnn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x05;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_7xnn(0x1, 0x10);
+        assert_eq!(chip8.registers[0x1], 0x15);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        let result = chip8.op_7xnn(0x1, 0xF0);
+        assert_eq!(chip8.registers[0x1], 0x05);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_8xy0() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x05;
+        chip8.registers[0x2] = 0x10;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy0(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x10);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_8xy1() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x07;
+        chip8.registers[0x2] = 0x0A;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy1(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x0F);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_8xy2() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x07;
+        chip8.registers[0x2] = 0x0A;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy2(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x02);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_8xy3() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x07;
+        chip8.registers[0x2] = 0x0A;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy3(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x0D);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_logic_vf_reset_quirk() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0xF] = 0x01;
+        chip8.op_8xy1(0x1, 0x2);
+        assert_eq!(chip8.registers[0xF], 0x00);
+
+        let mut chip8 = Chip8::new(Quirks::MODERN);
+        chip8.registers[0xF] = 0x01;
+        chip8.op_8xy2(0x1, 0x2);
+        assert_eq!(chip8.registers[0xF], 0x01);
+    }
+
+    #[test]
+    fn test_op_8xy4() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x07;
+        chip8.registers[0x2] = 0xFF;
+        chip8.registers[0x3] = 0x0A;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy4(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x06);
+        assert_eq!(chip8.registers[0xF], 0x01);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        let result = chip8.op_8xy4(0x1, 0x3);
+        assert_eq!(chip8.registers[0x1], 0x10);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_8xy5() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x07;
+        chip8.registers[0x2] = 0xFF;
+        chip8.registers[0x3] = 0x03;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy5(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x08);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        let result = chip8.op_8xy5(0x1, 0x3);
+        assert_eq!(chip8.registers[0x1], 0x05);
+        assert_eq!(chip8.registers[0xF], 0x01);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_8xy6() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x07;
+        chip8.registers[0x2] = 0xFF;
+        chip8.registers[0x3] = 0x06;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy6(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x7F);
+        assert_eq!(chip8.registers[0xF], 0x01);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        let result = chip8.op_8xy6(0x1, 0x3);
+        assert_eq!(chip8.registers[0x1], 0x03);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_8xy7() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x0F;
+        chip8.registers[0x2] = 0x07;
+        chip8.registers[0x3] = 0x08;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy7(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0xF8);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        let result = chip8.op_8xy7(0x2, 0x3);
+        assert_eq!(chip8.registers[0x2], 0x01);
+        assert_eq!(chip8.registers[0xF], 0x01);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_8xye() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x07;
+        chip8.registers[0x2] = 0xFF;
+        chip8.registers[0x3] = 0x06;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xye(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0xFE);
+        assert_eq!(chip8.registers[0xF], 0x01);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        let result = chip8.op_8xye(0x1, 0x3);
+        assert_eq!(chip8.registers[0x1], 0x0C);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_shift_in_place_quirk() {
+        let mut chip8 = Chip8::new(Quirks::MODERN);
+        chip8.registers[0x1] = 0x81;
+        chip8.registers[0x2] = 0x02;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_8xy6(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x40);
+        assert_eq!(chip8.registers[0xF], 0x01);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        let result = chip8.op_8xye(0x1, 0x2);
+        assert_eq!(chip8.registers[0x1], 0x80);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_9xy0() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x1] = 0x50;
+        chip8.registers[0x2] = 0x50;
+        chip8.registers[0x3] = 0x51;
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_9xy0(0x1, 0x2);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        let result = chip8.op_9xy0(0x1, 0x3);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
+    }
 
-        let result = chip8.op_5xy0(0x1, 0x3);
+    #[test]
+    fn test_op_annn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_annn( 0x99A);
+        assert_eq!(chip8.index_register, 0x99A);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_op_bnnn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+        chip8.registers[0x0] = 0x04;
+
+        let result = chip8.op_bnnn( 0x203);
+        assert_eq!(result, 0x207);
+    }
+
+    #[test]
+    fn test_jump_uses_vx_quirk() {
+        let mut chip8 = Chip8::new(Quirks::SUPER_CHIP_1_1);
+        chip8.registers[0x0] = 0x04;
+        chip8.registers[0x2] = 0x10;
+
+        let result = chip8.op_bnnn(0x203);
+        assert_eq!(result, 0x213);
+    }
+
+    #[test]
+    fn test_op_bxy0() {
+        let mut chip8 = chip8x();
+        chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x21;
+        chip8.registers[0x2] = 0x10;
+        chip8.registers[0x3] = 0x0C;
+
+        let result = chip8.op_bxy0(0x1, 0x3);
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                let inside = (8..24).contains(&x) && y < 8;
+                assert_eq!(chip8.zone_color(x, y), if inside { 0x4 } else { DEFAULT_ZONE_COLOR });
+            }
+        }
+        assert!(chip8.draw_flag);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.registers[0xF] = 0xF0;
+        chip8.registers[0x0] = 0xF7;
+        chip8.op_bxy0(0xF, 0x3);
+        assert_eq!(chip8.zone_color(SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1), 0x4);
+    }
+
+    #[test]
+    fn test_op_bxyn() {
+        let mut chip8 = chip8x();
+        chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x11;
+        chip8.registers[0x2] = 0x1E;
+        chip8.registers[0x3] = 0x07;
+
+        let result = chip8.op_bxyn(0x1, 0x3, 0x3);
+        assert_eq!(chip8.color_zones[0x1E][0x2], 0x7);
+        assert_eq!(chip8.color_zones[0x1F][0x2], 0x7);
+        assert_eq!(chip8.color_zones[0x00][0x2], 0x7);
+        assert_eq!(chip8.color_zones[0x01][0x2], DEFAULT_ZONE_COLOR);
+        assert_eq!(chip8.color_zones[0x1E][0x1], DEFAULT_ZONE_COLOR);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
+
+    #[test]
+    fn test_chip8x_replaces_bnnn() {
+        let mut chip8 = chip8x();
+        chip8.load_at(&[0xB1, 0x31], CHIP_8X_START_ADDRESS).unwrap();
+        chip8.registers[0x3] = 0x02;
+
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, CHIP_8X_START_ADDRESS + INSTRUCTION_SIZE);
+        assert_eq!(chip8.color_zones[0x0][0x0], 0x2);
+    }
+
+    #[test]
+    fn test_op_cxnn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+
+        let result = chip8.op_cxnn( 0x0, 0xF0);
+        assert_eq!(chip8.registers[0x0] & !0xF0, 0);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
     }
 
     #[test]
-    fn test_op_6xnn() {
-        let mut chip8 = Chip8::new();
+    fn test_op_cxnn_seeded() {
+        let seeded = |random| {
+            let mut chip8 = Chip8::with_config(Config {
+                random,
+                seed: Some(7),
+                ..Config::default()
+            });
+            (0..16)
+                .map(|_| {
+                    chip8.op_cxnn(0x0, 0xFF);
+                    chip8.registers[0x0]
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(seeded(RandomAlgorithm::Xorshift), seeded(RandomAlgorithm::Xorshift));
+        assert_eq!(seeded(RandomAlgorithm::Patterned), seeded(RandomAlgorithm::Patterned));
+        assert_ne!(seeded(RandomAlgorithm::Xorshift), seeded(RandomAlgorithm::Patterned));
+    }
+
+    #[test]
+    fn test_save_state_random_algorithm() {
+        let config = |random| Config { random, seed: Some(7), ..Config::default() };
+        let mut patterned = Chip8::with_config(config(RandomAlgorithm::Patterned));
+        let state = patterned.save_state();
+        patterned.op_cxnn(0x0, 0xFF);
+
+        let mut xorshift = Chip8::with_config(config(RandomAlgorithm::Xorshift));
+        xorshift.load_state(&state).unwrap();
+        xorshift.op_cxnn(0x0, 0xFF);
+        assert_eq!(xorshift.registers[0x0], patterned.registers[0x0]);
+        assert_eq!(xorshift.save_state(), patterned.save_state());
+    }
+
+    #[test]
+    fn test_set_random_source() {
+        /// A source that always returns the same byte
+        struct Constant(u8);
+
+        impl RandomSource for Constant {
+            fn next_byte(&mut self) -> u8 {
+                self.0
+            }
+
+            fn state(&self) -> u64 {
+                self.0 as u64
+            }
+
+            fn set_state(&mut self, state: u64) {
+                self.0 = state as u8;
+            }
+        }
+
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.set_random_source(Box::new(Constant(0xA5)));
+        chip8.op_cxnn(0x0, 0x0F);
+        assert_eq!(chip8.registers[0x0], 0x05);
+
+        let state = chip8.save_state();
+        chip8.set_random_source(Box::new(Constant(0x00)));
+        chip8.load_state(&state).unwrap();
+        chip8.op_cxnn(0x0, 0xFF);
+        assert_eq!(chip8.registers[0x0], 0xA5);
+    }
+
+    #[test]
+    fn test_op_dxyn() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+        chip8.registers[0x0] = 0x05;
+        chip8.registers[0x1] = 0x01;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300] = 0b11111111;
+        chip8.memory[0x301] = 0b10000001;
+        chip8.memory[0x302] = 0b10000001;
+        chip8.memory[0x303] = 0b11111111;
+
+        let result = chip8.op_dxyn(0x0, 0x01, 0x4);
+        assert_eq!(lores_screen(&chip8), [
+            0x0000000000000000,
+            0x07F8000000000000,
+            0x0408000000000000,
+            0x0408000000000000,
+            0x07F8000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+        ]);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        chip8.registers[0x3] = 0x02;
+        let result = chip8.op_dxyn(0x0, 0x3, 0x1);
+        assert_eq!(lores_screen(&chip8), [
+            0x0000000000000000,
+            0x07F8000000000000,
+            0x03F0000000000000,
+            0x0408000000000000,
+            0x07F8000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+            0x0000000000000000,
+        ]);
+        assert_eq!(chip8.registers[0xF], 0x01);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+        assert!(chip8.draw_flag);
+    }
+
+    #[test]
+    fn test_op_dxyn_out_of_bounds() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.index_register = MEMORY_SIZE - 2;
+
+        let result = chip8.op_dxyn(0x0, 0x1, 0x4);
+        assert_eq!(result, Err(Fault::MemoryOutOfBounds(MEMORY_SIZE)));
+    }
+
+    #[test]
+    fn test_op_dxyn_clips_at_edges() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x0] = 60;
+        chip8.registers[0x1] = 30;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x304].copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF]);
+
+        let result = chip8.op_dxyn(0x0, 0x1, 0x4);
+        assert_eq!(lores_screen(&chip8)[30], 0x000000000000000F);
+        assert_eq!(lores_screen(&chip8)[31], 0x000000000000000F);
+        assert_eq!(lores_screen(&chip8)[0], 0x0000000000000000);
+        assert_eq!(lores_screen(&chip8)[1], 0x0000000000000000);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+    }
+
+    #[test]
+    fn test_op_dxyn_wraps_at_edges() {
+        let mut chip8 = Chip8::new(Quirks::XO_CHIP);
+        chip8.registers[0x0] = 60;
+        chip8.registers[0x1] = 31;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x302].copy_from_slice(&[0xFF, 0x81]);
+
+        let result = chip8.op_dxyn(0x0, 0x1, 0x2);
+        assert_eq!(lores_screen(&chip8)[31], 0xF00000000000000F);
+        assert_eq!(lores_screen(&chip8)[0], 0x1000000000000008);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+    }
+
+    #[test]
+    fn test_op_dxyn_wraps_start_position() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.registers[0x0] = 64 + 2;
+        chip8.registers[0x1] = 32 + 3;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300] = 0xFF;
+
+        chip8.op_dxyn(0x0, 0x1, 0x1).unwrap();
+        assert_eq!(lores_screen(&chip8)[3], 0x3FC0000000000000);
+    }
+
+    /// Gets the columns or rows covered by a sprite `size` pixels long drawn from `position` on a screen `length` pixels long
+    fn sprite_span(position: u8, size: usize, length: usize, wrap: bool) -> Vec<usize> {
+        let start = position as usize % length;
+        (start..start + size)
+            .filter(|&pixel| wrap || pixel < length)
+            .map(|pixel| pixel % length)
+            .collect()
+    }
+
+    /// Draws a solid sprite from every value of the X register with the Y register at `y`,
+    /// and from every value of the Y register with the X register at `x`,
+    /// checking the pixels covered against `sprite_span` and that drawing it again erases it with a collision
+    fn check_solid_sprite_positions(mut chip8: Chip8, n: u8, x: u8, y: u8) {
+        let (sprite_width, sprite_height) = if n == 0 { (16, 16) } else { (8, n as usize) };
+        let (width, height) = (chip8.screen_width(), chip8.screen_height());
+        let wrap = chip8.quirks.sprites_wrap;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x320].fill(0xFF);
+
+        let positions = (0..=u8::MAX).map(|vx| (vx, y)).chain((0..=u8::MAX).map(|vy| (x, vy)));
+        for (vx, vy) in positions {
+            chip8.registers[0x0] = vx;
+            chip8.registers[0x1] = vy;
+            let mut expected = [0; HIRES_SCREEN_HEIGHT];
+            for row in sprite_span(vy, sprite_height, height, wrap) {
+                for column in sprite_span(vx, sprite_width, width, wrap) {
+                    expected[row] |= 1 << (HIRES_SCREEN_WIDTH - 1 - column);
+                }
+            }
+
+            chip8.op_dxyn(0x0, 0x1, n).unwrap();
+            assert_eq!(plane(&chip8, 0), expected, "drawing at {vx}, {vy}");
+            assert_eq!(chip8.registers[0xF], 0x00, "drawing at {vx}, {vy}");
+
+            chip8.op_dxyn(0x0, 0x1, n).unwrap();
+            assert_eq!(plane(&chip8, 0), [0; HIRES_SCREEN_HEIGHT], "erasing at {vx}, {vy}");
+            assert_eq!(chip8.registers[0xF], 0x01, "erasing at {vx}, {vy}");
+        }
+    }
+
+    #[test]
+    fn test_op_dxyn_every_position_clipped() {
+        check_solid_sprite_positions(Chip8::new(Quirks::COSMAC_VIP), 0xF, 60, 28);
+        check_solid_sprite_positions(Chip8::new(Quirks::COSMAC_VIP), 0x1, 63, 31);
+
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.two_page = true;
+        chip8.set_lores();
+        check_solid_sprite_positions(chip8, 0xF, 59, 60);
+
+        let mut chip8 = superchip();
+        chip8.op_00ff();
+        check_solid_sprite_positions(chip8, 0x0, 120, 56);
+    }
+
+    #[test]
+    fn test_op_dxyn_every_position_wrapped() {
+        check_solid_sprite_positions(xochip(), 0xF, 60, 28);
+        check_solid_sprite_positions(xochip(), 0x1, 63, 31);
+
+        for (n, x, y) in [(0x0, 120, 56), (0xF, 127, 63)] {
+            let mut chip8 = xochip();
+            chip8.op_00ff();
+            check_solid_sprite_positions(chip8, n, x, y);
+        }
+    }
+
+    #[test]
+    fn test_op_dxyn_at_last_pixel() {
+        for quirks in [Quirks::COSMAC_VIP, Quirks::XO_CHIP] {
+            let mut chip8 = Chip8::new(quirks);
+            chip8.registers[0x0] = 63;
+            chip8.registers[0x1] = 31;
+            chip8.index_register = 0x300;
+            chip8.memory[0x300..0x302].copy_from_slice(&[0xC0, 0xC0]);
+
+            chip8.op_dxyn(0x0, 0x1, 0x2).unwrap();
+            assert!(chip8.pixel(63, 31));
+            assert_eq!(chip8.pixel(0, 31), quirks.sprites_wrap);
+            assert_eq!(chip8.pixel(63, 0), quirks.sprites_wrap);
+            assert_eq!(chip8.pixel(0, 0), quirks.sprites_wrap);
+        }
+    }
+
+    #[test]
+    fn test_op_dxy0() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+        chip8.display.set_resolution(HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+        chip8.index_register = 0x300;
+        for row in 0..16 {
+            chip8.memory[0x300 + row * 2] = 0x80;
+            chip8.memory[0x300 + row * 2 + 1] = 0x01;
+        }
+        chip8.registers[0x0] = 120;
+        chip8.registers[0x1] = 56;
+
+        let result = chip8.op_dxyn(0x0, 0x1, 0x0);
+        for y in 56..HIRES_SCREEN_HEIGHT {
+            assert_eq!(plane(&chip8, 0)[y], 0x1 << 7);
+        }
+        assert_eq!(plane(&chip8, 0)[..56], [0; 56]);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        let mut chip8 = Chip8::new(Quirks::SUPER_CHIP_1_1);
+        chip8.index_register = 0x300;
+        chip8.memory[0x300] = 0xFF;
+        chip8.op_dxyn(0x0, 0x1, 0x0).unwrap();
+        assert_eq!(plane(&chip8, 0), [0; HIRES_SCREEN_HEIGHT]);
+    }
+
+    #[test]
+    fn test_lores_dxy0_quirk() {
+        for (quirks, expected) in [(Quirks::SUPER_CHIP_1_1, 0xFF << 120), (Quirks::XO_CHIP, 0xFFFF << 112)] {
+            let mut chip8 = Chip8::with_config(Config { variant: Variant::SuperChip, quirks, ..Config::default() });
+            chip8.index_register = 0x300;
+            chip8.memory[0x300..0x320].fill(0xFF);
+
+            chip8.op_dxyn(0x0, 0x1, 0x0).unwrap();
+            assert_eq!(plane(&chip8, 0)[..17], [[expected; 16].as_slice(), &[0]].concat());
+        }
+
+        // The hi-res screen always draws 16x16 sprites
+        let mut chip8 = superchip();
+        chip8.display.set_resolution(HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x320].fill(0xFF);
+        chip8.op_dxyn(0x0, 0x1, 0x0).unwrap();
+        assert_eq!(plane(&chip8, 0)[0], 0xFFFF << 112);
+    }
+
+    #[test]
+    fn test_op_dxyn_two_page() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.two_page = true;
+        chip8.set_lores();
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x302].copy_from_slice(&[0xFF, 0xFF]);
+        chip8.registers[0x0] = 0x08;
+        chip8.registers[0x1] = 0x3F;
+
+        chip8.op_dxyn(0x0, 0x1, 0x2).unwrap();
+        assert_eq!(plane(&chip8, 0)[0x3F], 0xFF << 112);
+        assert_eq!(plane(&chip8, 0)[0x0], 0);
+        assert!(chip8.pixel(8, 63));
+
+        chip8.registers[0x1] = 0x40;
+        chip8.op_dxyn(0x0, 0x1, 0x1).unwrap();
+        assert_eq!(plane(&chip8, 0)[0x0], 0xFF << 112);
+    }
+
+    #[test]
+    fn test_op_dxyn_planes() {
+        let mut chip8 = xochip();
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x304].copy_from_slice(&[0xF0, 0x0F, 0xFF, 0x00]);
+
+        chip8.selected_planes = 0b11;
+        let result = chip8.op_dxyn(0x0, 0x1, 0x2);
+        assert_eq!(plane(&chip8, 0)[0x0..0x2], [0xF0 << 120, 0x0F << 120]);
+        assert_eq!(plane(&chip8, 1)[0x0..0x2], [0xFF << 120, 0x00]);
+        assert_eq!(chip8.pixel_planes(0, 0), 0b11);
+        assert_eq!(chip8.pixel_planes(4, 0), 0b10);
+        assert_eq!(chip8.pixel_planes(4, 1), 0b01);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        chip8.selected_planes = 0b10;
+        chip8.op_dxyn(0x0, 0x1, 0x2).unwrap();
+        assert_eq!(plane(&chip8, 1)[0x0..0x2], [0x0F << 120, 0x0F << 120]);
+        assert_eq!(chip8.registers[0xF], 0x01);
+    }
+
+    #[test]
+    fn test_op_dxyn_megachip() {
+        let mut chip8 = megachip();
+        chip8.program_counter = 0x200;
+        chip8.megachip_mode = true;
+        chip8.palette[1] = 0xFF102030;
+        chip8.palette[2] = 0xFF405060;
+        chip8.sprite_width = 2;
+        chip8.sprite_height = 2;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300..0x304].copy_from_slice(&[0x01, 0x00, 0x02, 0x01]);
+        chip8.registers[0x0] = 0xFF;
+        chip8.registers[0x1] = 0xBE;
+
+        let result = chip8.op_dxyn(0x0, 0x1, 0x0);
+        let pixel = |x: usize, y: usize| y * MEGA_CHIP_SCREEN_WIDTH + x;
+        assert_eq!(chip8.megachip_pixels()[pixel(255, 190)], 0xFF102030);
+        assert_eq!(chip8.megachip_pixels()[pixel(255, 191)], 0xFF405060);
+        assert_eq!(chip8.megachip_indices[pixel(255, 191)], 0x02);
+        assert_eq!(chip8.megachip_pixels().iter().filter(|&&argb| argb != 0).count(), 2);
+        assert_eq!(chip8.registers[0xF], 0x00);
+        assert!(chip8.draw_flag);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+
+        chip8.collision_color = 0x02;
+        chip8.blend_mode = BlendMode::Additive;
+        chip8.op_dxyn(0x0, 0x1, 0x0).unwrap();
+        assert_eq!(chip8.megachip_pixels()[pixel(255, 190)], 0xFF204060);
+        assert_eq!(chip8.registers[0xF], 0x01);
+
+        chip8.registers[0x1] = 0x00;
+        chip8.op_dxyn(0x0, 0x1, 0x0).unwrap();
+        assert_eq!(chip8.registers[0xF], 0x00);
+    }
+
+    #[test]
+    fn test_op_dxyn_megachip_font() {
+        let mut chip8 = megachip();
+        chip8.megachip_mode = true;
+        chip8.registers[0x2] = 0x0;
+        chip8.op_fx29(0x2);
+        chip8.registers[0x0] = 0x10;
+        chip8.registers[0x1] = 0x20;
+
+        chip8.op_dxyn(0x0, 0x1, FONT_CHARACTER_SIZE as u8).unwrap();
+        let lit: Vec<bool> = (0x10..0x18).map(|x| chip8.pixel(x, 0x20)).collect();
+        assert_eq!(lit, [true, true, true, true, false, false, false, false]);
+        assert_eq!(chip8.megachip_pixels()[0x20 * MEGA_CHIP_SCREEN_WIDTH + 0x10], MONOCHROME_COLOR);
+        assert_eq!(chip8.registers[0xF], 0x00);
+
+        // Each row of a font character is read once
+        chip8.add_watchpoint(Watchpoint { addresses: 0..=0xFFF, reads: true, writes: false, stops: false });
+        chip8.op_dxyn(0x0, 0x1, FONT_CHARACTER_SIZE as u8).unwrap();
+        assert_eq!(chip8.take_watch_hits().len(), FONT_CHARACTER_SIZE);
+
+        // Other sprites below the program are palette indices
+        chip8.clear_watchpoints();
+        chip8.palette[1] = 0xFF10_2030;
+        chip8.sprite_width = 1;
+        chip8.sprite_height = 1;
+        chip8.op_annn(0x100);
+        chip8.memory[0x100] = 0x01;
+        chip8.op_dxyn(0x0, 0x1, 0x1).unwrap();
+        assert_eq!(chip8.megachip_pixels()[0x20 * MEGA_CHIP_SCREEN_WIDTH + 0x10], 0xFF10_2030);
+    }
+
+    #[test]
+    fn test_op_ex9e() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x0A;
 
-        let result = chip8.op_6xnn(0x1, 0x99);
-        assert_eq!(chip8.registers[0x1], 0x99);
+        let result = chip8.op_ex9e(0x1);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.keypad[0xA] = true;
+        let result = chip8.op_ex9e(0x1);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
     }
 
     #[test]
-    fn test_op_7xnn() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x05;
+    fn test_op_exa1() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x0A;
 
-        let result = chip8.op_7xnn(0x1, 0x10);
-        assert_eq!(chip8.registers[0x1], 0x15);
+        let result = chip8.op_exa1(0x1);
+        assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
+
+        chip8.keypad[0xA] = true;
+        let result = chip8.op_exa1(0x1);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
     }
 
     #[test]
-    fn test_op_8xy0() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x05;
-        chip8.registers[0x2] = 0x10;
+    fn test_op_exf2() {
+        let mut chip8 = chip8x();
         chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x0A;
 
-        let result = chip8.op_8xy0(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0x10);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+        chip8.press_key(0xA);
+        assert_eq!(chip8.op_exf2(0x1), 0x200 + INSTRUCTION_SIZE);
+
+        chip8.press_second_key(0xA);
+        assert_eq!(chip8.op_exf2(0x1), 0x200 + INSTRUCTION_SIZE * 2);
     }
 
     #[test]
-    fn test_op_8xy1() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x07;
-        chip8.registers[0x2] = 0x0A;
+    fn test_op_exf5() {
+        let mut chip8 = chip8x();
         chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x0A;
 
-        let result = chip8.op_8xy1(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0x0F);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+        assert_eq!(chip8.op_exf5(0x1), 0x200 + INSTRUCTION_SIZE * 2);
+
+        chip8.press_second_key(0xA);
+        assert_eq!(chip8.op_exf5(0x1), 0x200 + INSTRUCTION_SIZE);
+
+        chip8.release_second_key(0xA);
+        assert_eq!(chip8.op_exf5(0x1), 0x200 + INSTRUCTION_SIZE * 2);
     }
 
     #[test]
-    fn test_op_8xy2() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x07;
-        chip8.registers[0x2] = 0x0A;
-        chip8.program_counter = 0x200;
+    fn test_op_f000() {
+        let mut chip8 = xochip();
+        chip8.load(&[0xF0, 0x00, 0xAB, 0xCD]).unwrap();
 
-        let result = chip8.op_8xy2(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0x02);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.index_register, 0xABCD);
+        assert_eq!(chip8.program_counter, 0x200 + INSTRUCTION_SIZE * 2);
+
+        chip8.program_counter = XO_CHIP_MEMORY_SIZE - 2;
+        assert_eq!(chip8.op_f000(), Err(Fault::MemoryOutOfBounds(XO_CHIP_MEMORY_SIZE)));
     }
 
     #[test]
-    fn test_op_8xy3() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x07;
-        chip8.registers[0x2] = 0x0A;
+    fn test_skip_over_f000() {
+        let mut chip8 = xochip();
+        chip8.load(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x30, 0x00, 0x00, 0xE0]).unwrap();
+
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, 0x206);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, 0x20A);
+
+        let mut chip8 = superchip();
+        chip8.load(&[0x30, 0x00, 0xF0, 0x00]).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.program_counter, 0x204);
+    }
+
+    #[test]
+    fn test_op_fn01() {
+        let mut chip8 = xochip();
         chip8.program_counter = 0x200;
 
-        let result = chip8.op_8xy3(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0x0D);
+        let result = chip8.op_fn01(0x2);
+        assert_eq!(chip8.selected_planes, 0b10);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.op_fn01(0xF);
+        assert_eq!(chip8.selected_planes, 0b11);
     }
 
     #[test]
-    fn test_op_8xy4() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x07;
-        chip8.registers[0x2] = 0xFF;
-        chip8.registers[0x3] = 0x0A;
+    fn test_op_f002() {
+        let mut chip8 = xochip();
         chip8.program_counter = 0x200;
+        chip8.index_register = 0x300;
+        for offset in 0..AUDIO_PATTERN_SIZE {
+            chip8.memory[0x300 + offset] = offset as u8;
+        }
+        assert_eq!(chip8.audio_pattern(), None);
 
-        let result = chip8.op_8xy4(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0x06);
-        assert_eq!(chip8.registers[0xF], 0x01);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+        let result = chip8.op_f002();
+        assert_eq!(chip8.audio_pattern(), Some(&std::array::from_fn(|offset| offset as u8)));
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
 
-        let result = chip8.op_8xy4(0x1, 0x3);
-        assert_eq!(chip8.registers[0x1], 0x10);
-        assert_eq!(chip8.registers[0xF], 0x00);
+        chip8.index_register = XO_CHIP_MEMORY_SIZE - 1;
+        assert_eq!(chip8.op_f002(), Err(Fault::MemoryOutOfBounds(XO_CHIP_MEMORY_SIZE)));
+    }
+
+    #[test]
+    fn test_op_fx07() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+        chip8.delay_timer = 0x3C;
+
+        let result = chip8.op_fx07(0x1);
+        assert_eq!(chip8.registers[0x1], 0x3C);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
     }
 
     #[test]
-    fn test_op_8xy5() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x07;
-        chip8.registers[0x2] = 0xFF;
-        chip8.registers[0x3] = 0x03;
+    fn test_op_fx0a() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
 
-        let result = chip8.op_8xy5(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0x08);
-        assert_eq!(chip8.registers[0xF], 0x01);
+        let result = chip8.op_fx0a(0x1);
+        assert_eq!(result, 0x200);
+
+        chip8.keypad[0x7] = true;
+        let result = chip8.op_fx0a(0x1);
+        assert_eq!(chip8.awaited_key, Some(0x7));
+        assert_eq!(result, 0x200);
+
+        chip8.keypad[0x3] = true;
+        let result = chip8.op_fx0a(0x1);
+        assert_eq!(chip8.awaited_key, Some(0x7));
+        assert_eq!(result, 0x200);
+
+        chip8.keypad[0x7] = false;
+        let result = chip8.op_fx0a(0x1);
+        assert_eq!(chip8.registers[0x1], 0x07);
+        assert_eq!(chip8.awaited_key, None);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
 
-        let result = chip8.op_8xy5(0x1, 0x3);
+    #[test]
+    fn test_op_fx0a_tapped_key() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.load(&[0xF1, 0x0A, 0xF2, 0x0A]).unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::WaitingForKey));
+
+        // A key pressed and released before the next cycle is not lost
+        chip8.press_key(0x5);
+        chip8.release_key(0x5);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::WaitingForKey));
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
         assert_eq!(chip8.registers[0x1], 0x05);
-        assert_eq!(chip8.registers[0xF], 0x00);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        // Only presses since the previous cycle count
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::WaitingForKey));
+        chip8.set_keys([true; KEYPAD_SIZE]);
+        chip8.set_keys([false; KEYPAD_SIZE]);
+        chip8.emulate_cycle().unwrap();
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.registers[0x2], 0x00);
     }
 
     #[test]
-    fn test_op_8xy6() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x07;
-        chip8.registers[0x2] = 0xFF;
-        chip8.registers[0x3] = 0x06;
+    fn test_op_fx15() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x3C;
 
-        let result = chip8.op_8xy6(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0x7F);
-        assert_eq!(chip8.registers[0xF], 0x01);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
-
-        let result = chip8.op_8xy6(0x1, 0x3);
-        assert_eq!(chip8.registers[0x1], 0x03);
-        assert_eq!(chip8.registers[0xF], 0x00);
+        let result = chip8.op_fx15(0x1);
+        assert_eq!(chip8.delay_timer, 0x3C);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
     }
 
     #[test]
-    fn test_op_8xy7() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x0F;
-        chip8.registers[0x2] = 0x07;
-        chip8.registers[0x3] = 0x08;
+    fn test_op_fx18() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x3C;
 
-        let result = chip8.op_8xy7(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0xF8);
-        assert_eq!(chip8.registers[0xF], 0x01);
+        let result = chip8.op_fx18(0x1);
+        assert_eq!(chip8.sound_timer, 0x3C);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
 
-        let result = chip8.op_8xy7(0x2, 0x3);
-        assert_eq!(chip8.registers[0x2], 0x01);
-        assert_eq!(chip8.registers[0xF], 0x00);
+    #[test]
+    fn test_op_fx1e() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.program_counter = 0x200;
+        chip8.index_register = 0x300;
+        chip8.registers[0x1] = 0x20;
+
+        let result = chip8.op_fx1e(0x1);
+        assert_eq!(chip8.index_register, 0x320);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
     }
 
     #[test]
-    fn test_op_8xye() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x07;
-        chip8.registers[0x2] = 0xFF;
-        chip8.registers[0x3] = 0x06;
+    fn test_op_fx29() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x0B;
 
-        let result = chip8.op_8xye(0x1, 0x2);
-        assert_eq!(chip8.registers[0x1], 0xFE);
-        assert_eq!(chip8.registers[0xF], 0x01);
+        let result = chip8.op_fx29(0x1);
+        assert_eq!(chip8.index_register, FONT_ADDRESS + 0x0B * FONT_CHARACTER_SIZE);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+    }
 
-        let result = chip8.op_8xye(0x1, 0x3);
-        assert_eq!(chip8.registers[0x1], 0x0C);
-        assert_eq!(chip8.registers[0xF], 0x00);
+    #[test]
+    fn test_op_fx30() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x19;
+
+        let result = chip8.op_fx30(0x1);
+        assert_eq!(chip8.index_register, BIG_FONT_ADDRESS + 0x09 * BIG_FONT_CHARACTER_SIZE);
+        assert_eq!(
+            chip8.memory[chip8.index_register..chip8.index_register + BIG_FONT_CHARACTER_SIZE],
+            BIG_FONT[0x09 * BIG_FONT_CHARACTER_SIZE..0x0A * BIG_FONT_CHARACTER_SIZE]
+        );
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
     }
 
     #[test]
-    fn test_op_9xy0() {
-        let mut chip8 = Chip8::new();
-        chip8.registers[0x1] = 0x50;
-        chip8.registers[0x2] = 0x50;
-        chip8.registers[0x3] = 0x51;
+    fn test_op_fx33() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
+        chip8.index_register = 0x300;
+        chip8.registers[0x1] = 254;
 
-        let result = chip8.op_9xy0(0x1, 0x2);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+        let result = chip8.op_fx33(0x1);
+        assert_eq!(chip8.memory[0x300..0x303], [2, 5, 4]);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+    }
 
-        let result = chip8.op_9xy0(0x1, 0x3);
-        assert_eq!(result, 0x200 + INSTRUCTION_SIZE * 2);
+    #[test]
+    fn test_op_fx33_out_of_bounds() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.index_register = MEMORY_SIZE - 1;
+
+        let result = chip8.op_fx33(0x1);
+        assert_eq!(result, Err(Fault::MemoryOutOfBounds(MEMORY_SIZE)));
     }
 
     #[test]
-    fn test_op_annn() {
-        let mut chip8 = Chip8::new();
+    fn test_op_fx3a() {
+        let mut chip8 = xochip();
         chip8.program_counter = 0x200;
+        assert_eq!(chip8.audio_sample_rate(), 4000.0);
 
-        let result = chip8.op_annn( 0x99A);
-        assert_eq!(chip8.index_register, 0x99A);
+        chip8.registers[0x1] = 112;
+        let result = chip8.op_fx3a(0x1);
+        assert_eq!(chip8.pitch, 112);
+        assert_eq!(chip8.audio_sample_rate(), 8000.0);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
     }
 
     #[test]
-    fn test_op_bnnn() {
-        let mut chip8 = Chip8::new();
+    fn test_op_fx55() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
-        chip8.registers[0x0] = 0x04;
+        chip8.index_register = 0x300;
+        chip8.registers[0x0] = 0x01;
+        chip8.registers[0x1] = 0x02;
+        chip8.registers[0x2] = 0x03;
+        chip8.registers[0x3] = 0x04;
 
-        let result = chip8.op_bnnn( 0x203);
-        assert_eq!(result, 0x207);
+        let result = chip8.op_fx55(0x2);
+        assert_eq!(chip8.memory[0x300..0x304], [0x01, 0x02, 0x03, 0x00]);
+        assert_eq!(chip8.index_register, 0x303);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
     }
 
     #[test]
-    fn test_op_cxnn() {
-        let mut chip8 = Chip8::new();
+    fn test_op_fx55_out_of_bounds() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.index_register = MEMORY_SIZE - 2;
+
+        let result = chip8.op_fx55(0x3);
+        assert_eq!(result, Err(Fault::MemoryOutOfBounds(MEMORY_SIZE)));
+    }
+
+    #[test]
+    fn test_op_fx65() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
         chip8.program_counter = 0x200;
+        chip8.index_register = 0x300;
+        chip8.memory[0x300] = 0x01;
+        chip8.memory[0x301] = 0x02;
+        chip8.memory[0x302] = 0x03;
+        chip8.memory[0x303] = 0x04;
 
-        let result = chip8.op_cxnn( 0x0, 0xF0);
-        assert_eq!(chip8.registers[0x0] & !0xF0, 0);
+        let result = chip8.op_fx65(0x2);
+        assert_eq!(chip8.registers[0x0..0x4], [0x01, 0x02, 0x03, 0x00]);
+        assert_eq!(chip8.index_register, 0x303);
+        assert_eq!(result, Ok(0x200 + INSTRUCTION_SIZE));
+    }
+
+    #[test]
+    fn test_load_store_index_quirk() {
+        let mut chip8 = Chip8::new(Quirks::SUPER_CHIP_1_1);
+        chip8.index_register = 0x300;
+
+        chip8.op_fx55(0x2).unwrap();
+        assert_eq!(chip8.index_register, 0x300);
+
+        chip8.op_fx65(0x2).unwrap();
+        assert_eq!(chip8.index_register, 0x300);
+    }
+
+    #[test]
+    fn test_op_fx65_out_of_bounds() {
+        let mut chip8 = Chip8::new(Quirks::COSMAC_VIP);
+        chip8.index_register = MEMORY_SIZE;
+
+        let result = chip8.op_fx65(0x0);
+        assert_eq!(result, Err(Fault::MemoryOutOfBounds(MEMORY_SIZE)));
+    }
+
+    #[test]
+    fn test_op_fx75() {
+        let mut chip8 = superchip();
+        chip8.program_counter = 0x200;
+        chip8.registers = [0xAA; REGISTER_COUNT];
+        chip8.registers[0x0..0x3].copy_from_slice(&[0x01, 0x02, 0x03]);
+
+        let result = chip8.op_fx75(0x2);
+        assert_eq!(chip8.flags[..8], [0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00]);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
+
+        chip8.op_fx75(0xF);
+        assert_eq!(chip8.flags[..8], [0x01, 0x02, 0x03, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA]);
+        assert_eq!(chip8.flags[8..], [0x00; 8]);
+
+        let mut chip8 = xochip();
+        chip8.registers = [0xAA; REGISTER_COUNT];
+        chip8.op_fx75(0xF);
+        assert_eq!(chip8.flags, [0xAA; FLAG_COUNT]);
     }
 
     #[test]
-    fn test_op_dxyn() {
-        let mut chip8 = Chip8::new();
+    fn test_op_fx85() {
+        let mut chip8 = superchip();
         chip8.program_counter = 0x200;
-        chip8.registers[0x0] = 0x05;
-        chip8.registers[0x1] = 0x01;
-        chip8.index_register = 0x300;
-        chip8.memory[0x300] = 0b11111111;
-        chip8.memory[0x301] = 0b10000001;
-        chip8.memory[0x302] = 0b10000001;
-        chip8.memory[0x303] = 0b11111111;
+        chip8.flags[..8].copy_from_slice(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
 
-        let result = chip8.op_dxyn(0x0, 0x01, 0x4);
-        assert_eq!(chip8.screen, [
-            0x0000000000000000,
-            0x07F8000000000000,
-            0x0408000000000000,
-            0x0408000000000000,
-            0x07F8000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-        ]);
-        assert_eq!(chip8.registers[0xF], 0x00);
+        let result = chip8.op_fx85(0x1);
+        assert_eq!(chip8.registers[0x0..0x3], [0x01, 0x02, 0x00]);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
 
-        chip8.registers[0x3] = 0x02;
-        let result = chip8.op_dxyn(0x0, 0x3, 0x1);
-        assert_eq!(chip8.screen, [
-            0x0000000000000000,
-            0x07F8000000000000,
-            0x03F0000000000000,
-            0x0408000000000000,
-            0x07F8000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-            0x0000000000000000,
-        ]);
-        assert_eq!(chip8.registers[0xF], 0x01);
+        chip8.op_fx85(0xF);
+        assert_eq!(chip8.registers[0x0..0x8], chip8.flags[..8]);
+        assert_eq!(chip8.registers[0x8..], [0x00; 8]);
+    }
+
+    #[test]
+    fn test_op_fxf8() {
+        let mut chip8 = chip8x();
+        chip8.program_counter = 0x200;
+        chip8.registers[0x1] = 0x80;
+
+        let result = chip8.op_fxf8(0x1);
+        assert_eq!(chip8.take_output_port(), Some(0x80));
+        assert_eq!(chip8.take_output_port(), None);
         assert_eq!(result, 0x200 + INSTRUCTION_SIZE);
     }
+
+    #[test]
+    fn test_op_fxfb() {
+        let mut chip8 = chip8x();
+        chip8.load(&[0xF1, 0xFB]).unwrap();
+
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::WaitingForInput));
+        assert_eq!(chip8.program_counter, CHIP_8X_START_ADDRESS);
+
+        chip8.set_input_port(0x42);
+        assert_eq!(chip8.emulate_cycle(), Ok(CycleOutcome::Executed));
+        assert_eq!(chip8.registers[0x1], 0x42);
+        assert_eq!(chip8.program_counter, CHIP_8X_START_ADDRESS + INSTRUCTION_SIZE);
+        assert_eq!(chip8.input_port, None);
+    }
 }
@@ -0,0 +1,390 @@
+use std::path::{Path, PathBuf};
+
+use chip8::headless::{KeyEvent, Limit, ScreenFormat};
+use chip8::{KEYPAD_SIZE, Quirks, RandomAlgorithm, Variant};
+
+/// The usage message printed for `--help` and after argument errors
+pub const USAGE: &str = "\
+Usage: chip8 [OPTIONS] <ROM>
+
+Options:
+  --ipf <N>         Instructions emulated per frame [default: 10]
+  --scale <N>       Window pixels per CHIP-8 pixel [default: 10]
+  --start <ADDR>    Address to load and start the program at [default: 0x300 for chip8x, otherwise 0x200]
+  --variant <NAME>  Instruction set to emulate: chip8, hires, chip8x, schip, xochip or megachip
+                    [default: chip8]
+  --quirks <NAME>   Behavior of ambiguous instructions: vip, chip48, schip, xochip or modern
+                    [default: the reference interpreter of the variant]
+  --seed <N>        Seed for the random numbers of CXNN, for reproducible runs [default: random]
+  --random <NAME>   Random number algorithm: xorshift or patterned [default: xorshift]
+  --state <FILE>    Save state to restore before running, such as a quick-save slot
+  --headless        Run without opening a window until the program halts, then print the registers
+  --frames <N>      Stop a headless run after N frames [default: 36000, failing if the program hasn't halted]
+  --cycles <N>      Stop a headless run after N instructions
+  --keys <SCRIPT>   Keys to press and release in a headless run, such as '30+5,32-5' to press key 5
+                    before frame 30 and release it before frame 32
+  --screen <FILE>   Write the screen at the end of a headless run to a .png, .pbm or .txt file
+  --debug           Step through the program in an interactive debugger in the terminal
+  -h, --help        Print this message
+
+Numbers may be written in decimal or in hexadecimal with a 0x prefix.";
+
+/// The default number of instructions emulated between timer ticks
+const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 10;
+/// The default size of a single CHIP-8 pixel in window pixels
+const DEFAULT_SCALE: u32 = 10;
+
+/// The action requested on the command line
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Run a program with the given options
+    Run(Options),
+    /// Print the usage message
+    Help,
+}
+
+/// The options for running a program
+#[derive(Debug, PartialEq, Eq)]
+pub struct Options {
+    pub rom_path: PathBuf,
+    pub instructions_per_frame: usize,
+    pub scale: u32,
+    pub start_address: usize,
+    pub variant: Variant,
+    pub quirks: Quirks,
+    pub random: RandomAlgorithm,
+    /// The seed of the random numbers, or `None` to seed them from the operating system
+    pub seed: Option<u64>,
+    /// The save state restored after loading the program
+    pub state_path: Option<PathBuf>,
+    pub headless: bool,
+    /// The point a headless run stops at, or `None` to fail at `headless::DEFAULT_LIMIT` unless the program halts
+    pub limit: Option<Limit>,
+    /// The key events of a headless run
+    pub keys: Vec<KeyEvent>,
+    /// The file the screen is written to at the end of a headless run
+    pub screen: Option<(PathBuf, ScreenFormat)>,
+    pub debug: bool,
+}
+
+/// Parses the command line arguments, excluding the program name
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let mut rom_path = None;
+    let mut instructions_per_frame = DEFAULT_INSTRUCTIONS_PER_FRAME;
+    let mut scale = DEFAULT_SCALE;
+    let mut start_address = None;
+    let mut variant = Variant::default();
+    let mut quirks = None;
+    let mut random = RandomAlgorithm::default();
+    let mut seed = None;
+    let mut state_path = None;
+    let mut headless = false;
+    let mut limit = None;
+    let mut keys = Vec::new();
+    let mut screen = None;
+    let mut debug = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--ipf" => instructions_per_frame = parse_number(&arg, args.next())?,
+            "--scale" => scale = parse_number(&arg, args.next())?,
+            "--start" => start_address = Some(parse_number(&arg, args.next())?),
+            "--variant" => variant = parse_variant(&arg, args.next())?,
+            "--quirks" => quirks = Some(parse_quirks(&arg, args.next())?),
+            "--seed" => seed = Some(parse_number(&arg, args.next())?),
+            "--random" => random = parse_random(&arg, args.next())?,
+            "--state" => state_path = Some(PathBuf::from(args.next().ok_or("'--state' requires a value")?)),
+            "--headless" => headless = true,
+            "--frames" | "--cycles" if limit.is_some() => {
+                return Err("only one of '--frames' and '--cycles' can be given".to_string());
+            }
+            "--frames" => limit = Some(Limit::Frames(parse_number(&arg, args.next())?)),
+            "--cycles" => limit = Some(Limit::Cycles(parse_number(&arg, args.next())?)),
+            "--keys" => keys.extend(parse_keys(&arg, args.next())?),
+            "--screen" => screen = Some(parse_screen(&arg, args.next())?),
+            "--debug" => debug = true,
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{arg}'")),
+            _ if rom_path.is_some() => return Err(format!("unexpected argument '{arg}'")),
+            _ => rom_path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    if instructions_per_frame == 0 {
+        return Err("'--ipf' must be at least 1".to_string());
+    }
+    if scale == 0 {
+        return Err("'--scale' must be at least 1".to_string());
+    }
+    if headless && debug {
+        return Err("only one of '--headless' and '--debug' can be given".to_string());
+    }
+    if !headless && (limit.is_some() || !keys.is_empty() || screen.is_some()) {
+        return Err("'--frames', '--cycles', '--keys' and '--screen' require '--headless'".to_string());
+    }
+
+    Ok(Command::Run(Options {
+        rom_path: rom_path.ok_or("no ROM file given")?,
+        instructions_per_frame,
+        scale,
+        start_address: start_address.unwrap_or_else(|| variant.start_address()),
+        variant,
+        quirks: quirks.unwrap_or_else(|| variant.default_quirks()),
+        random,
+        seed,
+        state_path,
+        headless,
+        limit,
+        keys,
+        screen,
+        debug,
+    }))
+}
+
+/// Gets the path of quick-save `slot` for the program at `rom_path`, which sits next to the program
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+pub fn slot_path(rom_path: &Path, slot: usize) -> PathBuf {
+    rom_path.with_extension(format!("state{slot}"))
+}
+
+/// Parses the value given for `option` as the name of a variant
+fn parse_variant(option: &str, value: Option<String>) -> Result<Variant, String> {
+    let value = value.ok_or_else(|| format!("'{option}' requires a value"))?;
+    Variant::from_name(&value)
+        .ok_or_else(|| format!("unknown variant '{value}', expected one of: {}", Variant::NAMES.join(", ")))
+}
+
+/// Parses the value given for `option` as the name of a quirk preset
+fn parse_quirks(option: &str, value: Option<String>) -> Result<Quirks, String> {
+    let value = value.ok_or_else(|| format!("'{option}' requires a value"))?;
+    Quirks::preset(&value).ok_or_else(|| {
+        format!("unknown quirk profile '{value}', expected one of: {}", Quirks::PRESET_NAMES.join(", "))
+    })
+}
+
+/// Parses the value given for `option` as the name of a random number algorithm
+fn parse_random(option: &str, value: Option<String>) -> Result<RandomAlgorithm, String> {
+    let value = value.ok_or_else(|| format!("'{option}' requires a value"))?;
+    RandomAlgorithm::from_name(&value).ok_or_else(|| {
+        format!("unknown random algorithm '{value}', expected one of: {}", RandomAlgorithm::NAMES.join(", "))
+    })
+}
+
+/// Parses the value given for `option` as a comma separated list of key events,
+/// each a frame number followed by `+` to press or `-` to release and a hexadecimal key
+fn parse_keys(option: &str, value: Option<String>) -> Result<Vec<KeyEvent>, String> {
+    let value = value.ok_or_else(|| format!("'{option}' requires a value"))?;
+    value
+        .split(',')
+        .map(|event| {
+            let invalid = || format!("invalid key event '{event}' for '{option}'");
+            let (frame, key) = event.split_once(['+', '-']).ok_or_else(invalid)?;
+            let key = u8::from_str_radix(key, 16).ok().filter(|&key| (key as usize) < KEYPAD_SIZE).ok_or_else(invalid)?;
+            Ok(KeyEvent {
+                frame: frame.parse().map_err(|_| invalid())?,
+                key,
+                pressed: event.as_bytes()[frame.len()] == b'+',
+            })
+        })
+        .collect()
+}
+
+/// Parses the value given for `option` as the path of a screen dump, with the format taken from its extension
+fn parse_screen(option: &str, value: Option<String>) -> Result<(PathBuf, ScreenFormat), String> {
+    let path = PathBuf::from(value.ok_or_else(|| format!("'{option}' requires a value"))?);
+    let format = path.extension().and_then(|extension| ScreenFormat::from_extension(&extension.to_string_lossy()));
+    match format {
+        Some(format) => Ok((path, format)),
+        None => Err(format!(
+            "unknown screen format for '{}', expected one of: {}",
+            path.display(),
+            ScreenFormat::EXTENSIONS.join(", ")
+        )),
+    }
+}
+
+/// Parses the value given for `option` as a decimal or 0x-prefixed hexadecimal number
+fn parse_number<T: TryFrom<u64>>(option: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("'{option}' requires a value"))?;
+    let number = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    number
+        .ok()
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or_else(|| format!("invalid value '{value}' for '{option}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chip8::{CHIP_8X_START_ADDRESS, PROGRAM_START_ADDRESS};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        let command = parse(args(&["pong.ch8"]));
+        assert_eq!(
+            command,
+            Ok(Command::Run(Options {
+                rom_path: PathBuf::from("pong.ch8"),
+                instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+                scale: DEFAULT_SCALE,
+                start_address: PROGRAM_START_ADDRESS,
+                variant: Variant::Chip8,
+                quirks: Quirks::default(),
+                random: RandomAlgorithm::Xorshift,
+                seed: None,
+                state_path: None,
+                headless: false,
+                limit: None,
+                keys: Vec::new(),
+                screen: None,
+                debug: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let command = parse(args(&[
+            "--ipf", "20", "--scale", "0x8", "--start", "0x600", "--quirks", "schip", "--seed", "42",
+            "--random", "patterned", "--state", "game.state1", "--headless", "game.ch8",
+        ]));
+        assert_eq!(
+            command,
+            Ok(Command::Run(Options {
+                rom_path: PathBuf::from("game.ch8"),
+                instructions_per_frame: 20,
+                scale: 8,
+                start_address: 0x600,
+                variant: Variant::Chip8,
+                quirks: Quirks::SUPER_CHIP_1_1,
+                random: RandomAlgorithm::Patterned,
+                seed: Some(42),
+                state_path: Some(PathBuf::from("game.state1")),
+                headless: true,
+                limit: None,
+                keys: Vec::new(),
+                screen: None,
+                debug: false,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_parse_variant() {
+        let Ok(Command::Run(options)) = parse(args(&["--variant", "schip", "game.ch8"])) else {
+            panic!("expected a run command");
+        };
+        assert_eq!(options.variant, Variant::SuperChip);
+        assert_eq!(options.quirks, Quirks::SUPER_CHIP_1_1);
+
+        let Ok(Command::Run(options)) = parse(args(&["--quirks", "modern", "--variant", "schip", "game.ch8"])) else {
+            panic!("expected a run command");
+        };
+        assert_eq!(options.quirks, Quirks::MODERN);
+
+        let Ok(Command::Run(options)) = parse(args(&["--variant", "chip8x", "game.ch8"])) else {
+            panic!("expected a run command");
+        };
+        assert_eq!(options.start_address, CHIP_8X_START_ADDRESS);
+    }
+
+    #[test]
+    fn test_parse_headless() {
+        let Ok(Command::Run(options)) = parse(args(&[
+            "--headless", "--cycles", "1000", "--keys", "0+a,12-A", "--keys", "3+0", "--screen", "out.PNG", "game.ch8",
+        ])) else {
+            panic!("expected a run command");
+        };
+        assert_eq!(options.limit, Some(Limit::Cycles(1000)));
+        assert_eq!(
+            options.keys,
+            [
+                KeyEvent { frame: 0, key: 0xA, pressed: true },
+                KeyEvent { frame: 12, key: 0xA, pressed: false },
+                KeyEvent { frame: 3, key: 0x0, pressed: true },
+            ]
+        );
+        assert_eq!(options.screen, Some((PathBuf::from("out.PNG"), ScreenFormat::Png)));
+
+        let Ok(Command::Run(options)) = parse(args(&["--headless", "--frames", "60", "game.ch8"])) else {
+            panic!("expected a run command");
+        };
+        assert_eq!(options.limit, Some(Limit::Frames(60)));
+    }
+
+    #[test]
+    fn test_parse_debug() {
+        let Ok(Command::Run(options)) = parse(args(&["--debug", "game.ch8"])) else {
+            panic!("expected a run command");
+        };
+        assert!(options.debug);
+    }
+
+    #[test]
+    fn test_parse_help() {
+        assert_eq!(parse(args(&["game.ch8", "--help"])), Ok(Command::Help));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse(args(&[])), Err("no ROM file given".to_string()));
+        assert_eq!(parse(args(&["--ipf"])), Err("'--ipf' requires a value".to_string()));
+        assert_eq!(
+            parse(args(&["--scale", "big", "game.ch8"])),
+            Err("invalid value 'big' for '--scale'".to_string())
+        );
+        assert_eq!(parse(args(&["--ipf", "0", "game.ch8"])), Err("'--ipf' must be at least 1".to_string()));
+        assert_eq!(
+            parse(args(&["--quirks", "eti", "game.ch8"])),
+            Err("unknown quirk profile 'eti', expected one of: vip, chip48, schip, xochip, modern".to_string())
+        );
+        assert_eq!(
+            parse(args(&["--variant", "eti", "game.ch8"])),
+            Err("unknown variant 'eti', expected one of: chip8, hires, chip8x, schip, xochip, megachip".to_string())
+        );
+        assert_eq!(
+            parse(args(&["--random", "os", "game.ch8"])),
+            Err("unknown random algorithm 'os', expected one of: xorshift, patterned".to_string())
+        );
+        assert_eq!(
+            parse(args(&["--frames", "60", "game.ch8"])),
+            Err("'--frames', '--cycles', '--keys' and '--screen' require '--headless'".to_string())
+        );
+        assert_eq!(
+            parse(args(&["--frames", "60", "--cycles", "60", "game.ch8"])),
+            Err("only one of '--frames' and '--cycles' can be given".to_string())
+        );
+        assert_eq!(
+            parse(args(&["--headless", "--keys", "5+G", "game.ch8"])),
+            Err("invalid key event '5+G' for '--keys'".to_string())
+        );
+        assert_eq!(
+            parse(args(&["--headless", "--keys", "5", "game.ch8"])),
+            Err("invalid key event '5' for '--keys'".to_string())
+        );
+        assert_eq!(
+            parse(args(&["--headless", "--screen", "out.bmp", "game.ch8"])),
+            Err("unknown screen format for 'out.bmp', expected one of: png, pbm, txt".to_string())
+        );
+        assert_eq!(
+            parse(args(&["--headless", "--debug", "game.ch8"])),
+            Err("only one of '--headless' and '--debug' can be given".to_string())
+        );
+        assert_eq!(parse(args(&["--fast", "game.ch8"])), Err("unknown option '--fast'".to_string()));
+        assert_eq!(parse(args(&["a.ch8", "b.ch8"])), Err("unexpected argument 'b.ch8'".to_string()));
+    }
+
+    #[test]
+    fn test_slot_path() {
+        assert_eq!(slot_path(Path::new("roms/pong.ch8"), 1), Path::new("roms/pong.state1"));
+    }
+}
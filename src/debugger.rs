@@ -0,0 +1,583 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::chip8::{Chip8, CycleOutcome, KEYPAD_SIZE};
+use crate::error::Chip8Error;
+use crate::headless::{ScreenFormat, dump_registers};
+use crate::watch::Watchpoint;
+
+/// The most instructions a single command runs before giving control back,
+/// so that a program that never reaches a breakpoint cannot hang the debugger
+pub const MAX_RUN_CYCLES: u64 = 10_000_000;
+/// The number of bytes `mem` shows on each line
+const MEMORY_ROW_SIZE: usize = 16;
+/// The number of bytes `mem` shows when no length is given
+const DEFAULT_MEMORY_LENGTH: usize = 4 * MEMORY_ROW_SIZE;
+/// The number of instructions `list` shows when no count is given
+const DEFAULT_LIST_COUNT: usize = 8;
+
+/// The commands `Debugger::execute` accepts
+pub const HELP: &str = "\
+Commands, with addresses in hexadecimal:
+  s, step [N]          Run N instructions [default: 1]
+  n, next              Run the next instruction, running a 2NNN call until it returns
+  f, finish            Run until the current subroutine returns with 00EE
+  u, until <ADDR>      Run until the program counter reaches ADDR
+  c, continue          Run until a breakpoint or watchpoint
+  b, break <ADDR>      Set a breakpoint at ADDR
+  d, delete [ADDR]     Remove the breakpoint at ADDR, or every breakpoint
+  w, watch <ADDR> [LAST] [r|w|rw]
+                       Stop after an instruction reads or writes memory from ADDR to LAST [default: w]
+  t, trace <ADDR> [LAST] [r|w|rw]
+                       Show the reads or writes of memory from ADDR to LAST without stopping [default: w]
+  unwatch [N]          Remove watchpoint N, or every watchpoint
+  i, breakpoints       List the breakpoints and watchpoints
+  r, regs              Show the registers, index register, timers and stack
+  x, mem <ADDR> [LEN]  Show LEN bytes of memory from ADDR [default: 64]
+  l, list [ADDR] [N]   Explain N instructions from ADDR [default: 8 from the program counter]
+  screen               Show the screen
+  press <KEY>          Hold down a key
+  release <KEY>        Release a key
+  h, help              Show this message
+  q, quit              Exit
+An empty line repeats the last command.";
+
+/// The name and short name of every command in `HELP`, to tell unknown commands from ones with wrong arguments
+const COMMANDS: [&str; 32] = [
+    "s", "step", "n", "next", "f", "finish", "u", "until", "c", "continue", "b", "break", "d", "delete", "w", "watch",
+    "t", "trace", "unwatch", "i", "breakpoints", "r", "regs", "x", "mem", "l", "list", "screen", "press", "release",
+    "h", "help",
+];
+
+/// Why `Debugger::run` stopped running instructions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    /// The condition of the command was met
+    Done,
+    /// The instruction limit was reached
+    Limit,
+    /// The program counter reached a breakpoint
+    Breakpoint,
+    /// An instruction accessed memory watched by a stopping watchpoint
+    Watchpoint,
+    Halted,
+    WaitingForKey,
+    WaitingForInput,
+    Fault(Chip8Error),
+}
+
+/// An interactive debugger that runs commands against a processor one line at a time
+/// The timers tick once every `instructions_per_frame` instructions run
+pub struct Debugger {
+    instructions_per_frame: usize,
+    breakpoints: BTreeSet<usize>,
+    /// The number of instructions run so far
+    cycles: u64,
+    /// The command repeated by an empty line
+    last_command: String,
+}
+
+impl Debugger {
+    /// Creates a debugger with no breakpoints
+    pub fn new(instructions_per_frame: usize) -> Self {
+        Debugger {
+            instructions_per_frame: instructions_per_frame.max(1),
+            breakpoints: BTreeSet::new(),
+            cycles: 0,
+            last_command: String::new(),
+        }
+    }
+
+    /// Gets the number of instructions run so far
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Runs the command in `line` and returns its output, or `None` if the command was to quit
+    pub fn execute(&mut self, processor: &mut Chip8, line: &str) -> Option<String> {
+        let line = match line.trim() {
+            "" => self.last_command.clone(),
+            line => {
+                self.last_command = line.to_string();
+                line.to_string()
+            }
+        };
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let arguments: Vec<&str> = words.collect();
+        if matches!(command, "q" | "quit") {
+            return None;
+        }
+
+        let output = self.run_command(processor, command, &arguments);
+        Some(output.unwrap_or_else(|error| format!("error: {error}\n")))
+    }
+
+    /// Describes the instruction at the program counter of `processor`
+    pub fn location(&self, processor: &Chip8) -> String {
+        self.list(processor, processor.program_counter(), 1)
+    }
+
+    fn run_command(&mut self, processor: &mut Chip8, command: &str, arguments: &[&str]) -> Result<String, String> {
+        match (command, arguments) {
+            ("", []) => Ok(String::new()),
+            ("s" | "step", [] | [_]) => {
+                let count = arguments.first().map_or(Ok(1), |count| parse_count(count))?;
+                let stop = match self.run(processor, count, |_| false) {
+                    Stop::Limit => Stop::Done,
+                    stop => stop,
+                };
+                Ok(self.report(processor, stop))
+            }
+            ("n" | "next", []) => {
+                let address = processor.program_counter();
+                // 2NNN is a call under every variant
+                let is_call = read_opcode(processor, address).is_some_and(|opcode| opcode & 0xF000 == 0x2000);
+                let depth = processor.stack().len();
+                let stop = if is_call {
+                    self.run(processor, MAX_RUN_CYCLES, |processor| {
+                        processor.program_counter() == address + 2 && processor.stack().len() <= depth
+                    })
+                } else {
+                    self.run(processor, 1, |_| true)
+                };
+                Ok(self.report(processor, stop))
+            }
+            ("f" | "finish", []) => {
+                let depth = processor.stack().len();
+                if depth == 0 {
+                    return Err("not in a subroutine".to_string());
+                }
+                let stop = self.run(processor, MAX_RUN_CYCLES, |processor| processor.stack().len() < depth);
+                Ok(self.report(processor, stop))
+            }
+            ("u" | "until", [address]) => {
+                let address = parse_address(address)?;
+                let stop = self.run(processor, MAX_RUN_CYCLES, |processor| processor.program_counter() == address);
+                Ok(self.report(processor, stop))
+            }
+            ("c" | "continue", []) => {
+                let stop = self.run(processor, MAX_RUN_CYCLES, |_| false);
+                Ok(self.report(processor, stop))
+            }
+            ("b" | "break", [address]) => {
+                let address = parse_address(address)?;
+                self.breakpoints.insert(address);
+                Ok(format!("breakpoint at {address:#06X}\n"))
+            }
+            ("d" | "delete", []) => {
+                self.breakpoints.clear();
+                Ok("deleted every breakpoint\n".to_string())
+            }
+            ("d" | "delete", [address]) => {
+                let address = parse_address(address)?;
+                if !self.breakpoints.remove(&address) {
+                    return Err(format!("no breakpoint at {address:#06X}"));
+                }
+                Ok(format!("deleted the breakpoint at {address:#06X}\n"))
+            }
+            ("w" | "watch" | "t" | "trace", [_] | [_, _] | [_, _, _]) => {
+                let watchpoint = parse_watchpoint(processor, command, arguments)?;
+                let description = describe_watchpoint(&watchpoint);
+                processor.add_watchpoint(watchpoint);
+                Ok(format!("watchpoint {}: {description}\n", processor.watchpoints().len() - 1))
+            }
+            ("unwatch", []) => {
+                processor.clear_watchpoints();
+                Ok("deleted every watchpoint\n".to_string())
+            }
+            ("unwatch", [index]) => {
+                let index = parse_count(index)?;
+                if processor.remove_watchpoint(index).is_none() {
+                    return Err(format!("no watchpoint {index}"));
+                }
+                Ok(format!("deleted watchpoint {index}\n"))
+            }
+            ("i" | "breakpoints", []) if self.breakpoints.is_empty() && processor.watchpoints().is_empty() => {
+                Ok("no breakpoints or watchpoints\n".to_string())
+            }
+            ("i" | "breakpoints", []) => {
+                let mut listing: String =
+                    self.breakpoints.iter().map(|&address| self.list(processor, address, 1)).collect();
+                for (index, watchpoint) in processor.watchpoints().iter().enumerate() {
+                    writeln!(listing, "watchpoint {index}: {}", describe_watchpoint(watchpoint)).unwrap();
+                }
+                Ok(listing)
+            }
+            ("r" | "regs", []) => Ok(format!("{}Cycles {}\n", dump_registers(processor), self.cycles)),
+            ("x" | "mem", [address] | [address, _]) => {
+                let address = parse_address(address)?;
+                let length = arguments.get(1).map_or(Ok(DEFAULT_MEMORY_LENGTH), |length| parse_count(length))?;
+                dump_memory(processor.memory(), address, length)
+            }
+            ("l" | "list", [] | [_] | [_, _]) => {
+                let address = arguments.first().map_or(Ok(processor.program_counter()), |address| parse_address(address));
+                let address = address?;
+                let count = arguments.get(1).map_or(Ok(DEFAULT_LIST_COUNT), |count| parse_count(count))?;
+                Ok(self.list(processor, address, count))
+            }
+            ("screen", []) => Ok(String::from_utf8(ScreenFormat::Ascii.encode(processor)).unwrap()),
+            ("press", [key]) => {
+                processor.press_key(parse_key(key)?);
+                Ok(String::new())
+            }
+            ("release", [key]) => {
+                processor.release_key(parse_key(key)?);
+                Ok(String::new())
+            }
+            ("h" | "help", []) => Ok(format!("{HELP}\n")),
+            _ if COMMANDS.contains(&command) => Err(format!("wrong arguments for '{command}', see 'help'")),
+            _ => Err(format!("unknown command '{command}', see 'help'")),
+        }
+    }
+
+    /// Runs up to `limit` instructions, stopping after one for which `done` returns true,
+    /// before one at a breakpoint other than the first, or when the program halts, faults or waits
+    fn run(&mut self, processor: &mut Chip8, limit: u64, mut done: impl FnMut(&Chip8) -> bool) -> Stop {
+        for executed in 0..limit {
+            if executed > 0 && self.breakpoints.contains(&processor.program_counter()) {
+                return Stop::Breakpoint;
+            }
+
+            let outcome = match processor.run_cycles(1) {
+                Ok((outcome, _)) => outcome,
+                Err(error) => return Stop::Fault(error),
+            };
+            self.cycles += 1;
+            if self.cycles.is_multiple_of(self.instructions_per_frame as u64) {
+                processor.tick_timers();
+            }
+
+            match outcome {
+                CycleOutcome::Executed if done(processor) => return Stop::Done,
+                CycleOutcome::Executed => {}
+                CycleOutcome::WaitingForKey => return Stop::WaitingForKey,
+                CycleOutcome::WaitingForInput => return Stop::WaitingForInput,
+                CycleOutcome::Halted => return Stop::Halted,
+                CycleOutcome::Watchpoint => return Stop::Watchpoint,
+            }
+        }
+        Stop::Limit
+    }
+
+    /// Describes the accesses caught by the watchpoints during a run and why it stopped,
+    /// followed by the instruction it stopped at
+    fn report(&self, processor: &mut Chip8, stop: Stop) -> String {
+        let hits: String = processor.take_watch_hits().iter().map(|hit| format!("{hit}\n")).collect();
+        let reason = match stop {
+            Stop::Done => String::new(),
+            Stop::Limit => format!("stopped after {MAX_RUN_CYCLES} instructions\n"),
+            Stop::Breakpoint => "breakpoint\n".to_string(),
+            Stop::Watchpoint => "watchpoint\n".to_string(),
+            Stop::Halted => "the program halted\n".to_string(),
+            Stop::WaitingForKey => "waiting for a key, use 'press' and 'release'\n".to_string(),
+            Stop::WaitingForInput => "waiting for a value on the input port\n".to_string(),
+            Stop::Fault(error) => format!("fault: {error}\n"),
+        };
+        hits + &reason + &self.location(processor)
+    }
+
+    /// Explains `count` instructions starting at `address`, one per line,
+    /// marking the program counter with `=>` and breakpoints with `*`
+    fn list(&self, processor: &Chip8, mut address: usize, count: usize) -> String {
+        let mut listing = String::new();
+        for _ in 0..count {
+            let Some(opcode) = read_opcode(processor, address) else {
+                break;
+            };
+            let marker = if address == processor.program_counter() { "=>" } else { "  " };
+            let breakpoint = if self.breakpoints.contains(&address) { '*' } else { ' ' };
+            let (pattern, explanation) =
+                processor.describe_instruction(address, opcode).unwrap_or(("????", "Unknown instruction"));
+            writeln!(listing, "{marker}{breakpoint}{address:#06X}  {opcode:04X}  {pattern:<9}  {explanation}").unwrap();
+            // Patterns with a second word, like F000 NNNN, take up two instructions
+            address += if pattern.contains(' ') { 4 } else { 2 };
+        }
+        listing
+    }
+}
+
+/// Reads the opcode at `address`, or `None` if it is outside of memory
+fn read_opcode(processor: &Chip8, address: usize) -> Option<u16> {
+    let bytes = processor.memory().get(address..address + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Formats `length` bytes of `memory` from `address` in rows of `MEMORY_ROW_SIZE`
+fn dump_memory(memory: &[u8], address: usize, length: usize) -> Result<String, String> {
+    if address >= memory.len() {
+        return Err(format!("{address:#06X} is outside of memory"));
+    }
+
+    let bytes = &memory[address..memory.len().min(address + length)];
+    let mut dump = String::new();
+    for (row, bytes) in bytes.chunks(MEMORY_ROW_SIZE).enumerate() {
+        let bytes: Vec<String> = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+        writeln!(dump, "{:#06X}  {}", address + row * MEMORY_ROW_SIZE, bytes.join(" ")).unwrap();
+    }
+    Ok(dump)
+}
+
+/// Parses a hexadecimal address, with or without a 0x prefix
+fn parse_address(value: &str) -> Result<usize, String> {
+    let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value);
+    usize::from_str_radix(digits, 16).map_err(|_| format!("invalid address '{value}'"))
+}
+
+/// Parses the arguments of `watch` or `trace`, an address, an optional last address and an optional access,
+/// into a watchpoint inside the memory of `processor`
+fn parse_watchpoint(processor: &Chip8, command: &str, arguments: &[&str]) -> Result<Watchpoint, String> {
+    let (access, addresses) = match arguments.split_last() {
+        Some((&access @ ("r" | "w" | "rw"), addresses)) if !addresses.is_empty() => (access, addresses),
+        _ => ("w", arguments),
+    };
+    let (first, last) = match *addresses {
+        [address] => (parse_address(address)?, parse_address(address)?),
+        [first, last] => (parse_address(first)?, parse_address(last)?),
+        _ => return Err(format!("wrong arguments for '{command}', see 'help'")),
+    };
+    if first > last {
+        return Err(format!("{last:#06X} is before {first:#06X}"));
+    }
+    if last >= processor.memory().len() {
+        return Err(format!("{last:#06X} is outside of memory"));
+    }
+
+    Ok(Watchpoint {
+        addresses: first..=last,
+        reads: access.contains('r'),
+        writes: access.contains('w'),
+        stops: matches!(command, "w" | "watch"),
+    })
+}
+
+/// Describes the addresses, accesses and action of `watchpoint`
+fn describe_watchpoint(watchpoint: &Watchpoint) -> String {
+    let accesses = match (watchpoint.reads, watchpoint.writes) {
+        (true, true) => "reads and writes",
+        (true, false) => "reads",
+        _ => "writes",
+    };
+    let action = if watchpoint.stops { "stops" } else { "traces" };
+    let (first, last) = (watchpoint.addresses.start(), watchpoint.addresses.end());
+    if first == last {
+        format!("{action} on {accesses} of {first:#06X}")
+    } else {
+        format!("{action} on {accesses} of {first:#06X} to {last:#06X}")
+    }
+}
+
+/// Parses a decimal count
+fn parse_count<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid count '{value}'"))
+}
+
+/// Parses a hexadecimal key
+fn parse_key(value: &str) -> Result<u8, String> {
+    u8::from_str_radix(value, 16)
+        .ok()
+        .filter(|&key| (key as usize) < KEYPAD_SIZE)
+        .ok_or_else(|| format!("invalid key '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quirks::Quirks;
+
+    /// A program that calls a subroutine at 0x208 twice and then halts
+    const PROGRAM: [u8; 14] = [0x22, 0x08, 0x22, 0x08, 0x12, 0x04, 0x00, 0x00, 0x70, 0x01, 0x71, 0x02, 0x00, 0xEE];
+
+    fn processor() -> Chip8 {
+        let mut processor = Chip8::new(Quirks::MODERN);
+        processor.load(&PROGRAM).unwrap();
+        processor
+    }
+
+    /// Runs `line` and returns its output
+    fn execute(debugger: &mut Debugger, processor: &mut Chip8, line: &str) -> String {
+        debugger.execute(processor, line).unwrap()
+    }
+
+    #[test]
+    fn test_step() {
+        let mut processor = processor();
+        let mut debugger = Debugger::new(10);
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "step"),
+            "=> 0x0208  7001  7XNN       Adds nn to registers[x], wrapping on overflow\n"
+        );
+        execute(&mut debugger, &mut processor, "s 2");
+        assert_eq!(processor.program_counter(), 0x20C);
+        assert_eq!(processor.registers()[0x0], 0x01);
+
+        // An empty line repeats the last command
+        execute(&mut debugger, &mut processor, "");
+        assert_eq!(processor.program_counter(), 0x208);
+        execute(&mut debugger, &mut processor, "");
+        assert_eq!(processor.program_counter(), 0x20C);
+        assert_eq!(processor.registers()[0x0], 0x02);
+        assert_eq!(debugger.cycles(), 7);
+    }
+
+    #[test]
+    fn test_next_and_finish() {
+        let mut processor = processor();
+        let mut debugger = Debugger::new(10);
+        execute(&mut debugger, &mut processor, "next");
+        assert_eq!(processor.program_counter(), 0x202);
+        assert_eq!(processor.registers()[0x1], 0x02);
+
+        execute(&mut debugger, &mut processor, "step");
+        execute(&mut debugger, &mut processor, "next");
+        assert_eq!(processor.program_counter(), 0x20A);
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "finish"),
+            "=> 0x0204  1204  1NNN       Sets the program counter to address nnn\n"
+        );
+        assert_eq!(processor.registers()[0x1], 0x04);
+        assert_eq!(execute(&mut debugger, &mut processor, "finish"), "error: not in a subroutine\n");
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut processor = processor();
+        let mut debugger = Debugger::new(10);
+        assert_eq!(execute(&mut debugger, &mut processor, "break 20a"), "breakpoint at 0x020A\n");
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "continue"),
+            "breakpoint\n=>*0x020A  7102  7XNN       Adds nn to registers[x], wrapping on overflow\n"
+        );
+        assert_eq!(execute(&mut debugger, &mut processor, "c").lines().next(), Some("breakpoint"));
+        assert_eq!(processor.registers()[0x0], 0x02);
+
+        assert_eq!(execute(&mut debugger, &mut processor, "delete 0x20A"), "deleted the breakpoint at 0x020A\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "i"), "no breakpoints or watchpoints\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "c").lines().next(), Some("the program halted"));
+        assert_eq!(execute(&mut debugger, &mut processor, "delete 20A"), "error: no breakpoint at 0x020A\n");
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut processor = Chip8::new(Quirks::MODERN);
+        // Stores V0 through V1 at 0x300 and 0x301 with FX55, reads them back with FX65 and halts
+        processor.load(&[0x60, 0x07, 0xA3, 0x00, 0xF1, 0x55, 0xF1, 0x65, 0x12, 0x08]).unwrap();
+        let mut debugger = Debugger::new(10);
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "watch 301"),
+            "watchpoint 0: stops on writes of 0x0301\n"
+        );
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "trace 300 301 rw"),
+            "watchpoint 1: traces on reads and writes of 0x0300 to 0x0301\n"
+        );
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "c"),
+            "0x0204 wrote 0x07 to 0x0300\n\
+             0x0204 wrote 0x00 to 0x0301\n\
+             watchpoint\n\
+             => 0x0206  F165  FX65       Loads registers[0x0] through registers[x] from memory starting at index_register\n"
+        );
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "i"),
+            "watchpoint 0: stops on writes of 0x0301\n\
+             watchpoint 1: traces on reads and writes of 0x0300 to 0x0301\n"
+        );
+
+        assert_eq!(execute(&mut debugger, &mut processor, "unwatch 0"), "deleted watchpoint 0\n");
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "c"),
+            "0x0206 read 0x07 from 0x0300\n\
+             0x0206 read 0x00 from 0x0301\n\
+             the program halted\n\
+             => 0x0208  1208  1NNN       Sets the program counter to address nnn\n"
+        );
+        assert_eq!(execute(&mut debugger, &mut processor, "unwatch 1"), "error: no watchpoint 1\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "unwatch"), "deleted every watchpoint\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "i"), "no breakpoints or watchpoints\n");
+
+        assert_eq!(execute(&mut debugger, &mut processor, "w 302 301"), "error: 0x0301 is before 0x0302\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "w 1000"), "error: 0x1000 is outside of memory\n");
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "t 300 301 302"),
+            "error: wrong arguments for 't', see 'help'\n"
+        );
+    }
+
+    #[test]
+    fn test_until() {
+        let mut processor = processor();
+        let mut debugger = Debugger::new(10);
+        execute(&mut debugger, &mut processor, "until 0x204");
+        assert_eq!(processor.program_counter(), 0x204);
+        assert_eq!(processor.registers()[0x1], 0x04);
+    }
+
+    #[test]
+    fn test_waiting_and_faults() {
+        let mut processor = Chip8::new(Quirks::MODERN);
+        processor.load(&[0xF0, 0x0A, 0x00, 0xEE]).unwrap();
+        let mut debugger = Debugger::new(10);
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "c").lines().next(),
+            Some("waiting for a key, use 'press' and 'release'")
+        );
+        execute(&mut debugger, &mut processor, "press a");
+        execute(&mut debugger, &mut processor, "step");
+        execute(&mut debugger, &mut processor, "release a");
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "c").lines().next(),
+            Some("fault: stack underflow (opcode 00EE at 0x202)")
+        );
+        assert_eq!(processor.registers()[0x0], 0xA);
+    }
+
+    #[test]
+    fn test_timers() {
+        let mut processor = Chip8::new(Quirks::MODERN);
+        processor.load(&[0x60, 0x05, 0xF0, 0x15, 0x70, 0x01, 0x12, 0x04]).unwrap();
+        let mut debugger = Debugger::new(2);
+        execute(&mut debugger, &mut processor, "step 2");
+        assert_eq!(processor.delay_timer(), 0x04);
+        execute(&mut debugger, &mut processor, "step 4");
+        assert_eq!(processor.delay_timer(), 0x02);
+    }
+
+    #[test]
+    fn test_list() {
+        let mut processor = processor();
+        let mut debugger = Debugger::new(10);
+        execute(&mut debugger, &mut processor, "b 202");
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "list 200 4"),
+            "=> 0x0200  2208  2NNN       Calls the subroutine at address nnn\n\
+             \x20\x20*0x0202  2208  2NNN       Calls the subroutine at address nnn\n\
+             \x20\x20 0x0204  1204  1NNN       Sets the program counter to address nnn\n\
+             \x20\x20 0x0206  0000  ????       Unknown instruction\n"
+        );
+        assert_eq!(execute(&mut debugger, &mut processor, "l FFE 3").lines().count(), 1);
+    }
+
+    #[test]
+    fn test_mem() {
+        let mut processor = processor();
+        let mut debugger = Debugger::new(10);
+        assert_eq!(
+            execute(&mut debugger, &mut processor, "x 200 18"),
+            "0x0200  22 08 22 08 12 04 00 00 70 01 71 02 00 EE 00 00\n0x0210  00 00\n"
+        );
+        assert_eq!(execute(&mut debugger, &mut processor, "mem 1000"), "error: 0x1000 is outside of memory\n");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut processor = processor();
+        let mut debugger = Debugger::new(10);
+        assert_eq!(execute(&mut debugger, &mut processor, "jump"), "error: unknown command 'jump', see 'help'\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "break"), "error: wrong arguments for 'break', see 'help'\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "b xyz"), "error: invalid address 'xyz'\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "step many"), "error: invalid count 'many'\n");
+        assert_eq!(execute(&mut debugger, &mut processor, "press 10"), "error: invalid key '10'\n");
+        assert!(debugger.execute(&mut processor, "quit").is_none());
+    }
+}
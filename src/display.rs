@@ -0,0 +1,280 @@
+use crate::chip8::{HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH, PLANE_COUNT, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// The RGBA color of pixels lit in each combination of bitplanes, indexed by the bits of `Chip8::pixel_planes`
+/// Programs that only use the first plane are drawn in white on black
+pub const PLANE_COLORS: [[u8; 4]; 1 << PLANE_COUNT] = [
+    [0x00, 0x00, 0x00, 0xFF],
+    [0xFF, 0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA, 0xFF],
+    [0x55, 0x55, 0x55, 0xFF],
+];
+
+/// A screen of `PLANE_COUNT` monochrome bitplanes that the processor draws to
+/// Only `width`, `height`, `set_resolution`, `pixel` and `set_pixel` are required,
+/// but implementations can override the other methods with faster versions
+pub trait Display {
+    /// Gets the width of the screen in pixels
+    fn width(&self) -> usize;
+
+    /// Gets the height of the screen in pixels
+    fn height(&self) -> usize;
+
+    /// Changes the size of the screen to `width` by `height` pixels and clears every plane
+    fn set_resolution(&mut self, width: usize, height: usize);
+
+    /// Returns whether the pixel at column `x` and row `y` of `plane` is lit
+    /// Coordinates outside the screen are never lit
+    fn pixel(&self, plane: usize, x: usize, y: usize) -> bool;
+
+    /// Lights or clears the pixel at column `x` and row `y` of `plane`
+    /// Coordinates outside the screen are ignored
+    fn set_pixel(&mut self, plane: usize, x: usize, y: usize, lit: bool);
+
+    /// Flips the pixel at column `x` and row `y` of `plane` and returns whether it was lit before
+    fn toggle_pixel(&mut self, plane: usize, x: usize, y: usize) -> bool {
+        let was_lit = self.pixel(plane, x, y);
+        self.set_pixel(plane, x, y, !was_lit);
+        was_lit
+    }
+
+    /// Clears every pixel of `plane`
+    fn clear(&mut self, plane: usize) {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                self.set_pixel(plane, x, y, false);
+            }
+        }
+    }
+
+    /// Moves the contents of `plane` right by `dx` and down by `dy` pixels
+    /// Pixels moved past the edges are lost and the uncovered pixels are cleared
+    fn scroll(&mut self, plane: usize, dx: isize, dy: isize) {
+        let (width, height) = (self.width() as isize, self.height() as isize);
+        let columns: Vec<isize> = if dx > 0 { (0..width).rev().collect() } else { (0..width).collect() };
+        let rows: Vec<isize> = if dy > 0 { (0..height).rev().collect() } else { (0..height).collect() };
+
+        for &y in &rows {
+            for &x in &columns {
+                let (source_x, source_y) = (x - dx, y - dy);
+                let lit = (0..width).contains(&source_x)
+                    && (0..height).contains(&source_y)
+                    && self.pixel(plane, source_x as usize, source_y as usize);
+                self.set_pixel(plane, x as usize, y as usize, lit);
+            }
+        }
+    }
+
+    /// Gets the planes the pixel at column `x` and row `y` is lit in, with plane `n` in bit `n`
+    fn planes_at(&self, x: usize, y: usize) -> u8 {
+        (0..PLANE_COUNT)
+            .filter(|&plane| self.pixel(plane, x, y))
+            .fold(0, |planes, plane| planes | (1 << plane))
+    }
+}
+
+/// The default display, storing each row of each plane as the bits of a `u128`
+/// The leftmost pixel is in the most significant bit, and resolutions up to 128x64 are supported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    planes: [[u128; HIRES_SCREEN_HEIGHT]; PLANE_COUNT],
+}
+
+impl Bitmap {
+    /// Creates a cleared 64x32 display
+    pub fn new() -> Self {
+        Bitmap {
+            width: SCREEN_WIDTH,
+            height: SCREEN_HEIGHT,
+            planes: [[0; HIRES_SCREEN_HEIGHT]; PLANE_COUNT],
+        }
+    }
+
+    /// Gets the bit of a row that holds the pixel in column `x`
+    fn pixel_mask(x: usize) -> u128 {
+        1 << (HIRES_SCREEN_WIDTH - 1 - x)
+    }
+
+    /// Gets the bits of a row that are inside the screen
+    fn row_mask(&self) -> u128 {
+        u128::MAX << (HIRES_SCREEN_WIDTH - self.width)
+    }
+}
+
+impl Default for Bitmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Bitmap {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Panics if the resolution is larger than 128x64
+    fn set_resolution(&mut self, width: usize, height: usize) {
+        assert!(width <= HIRES_SCREEN_WIDTH && height <= HIRES_SCREEN_HEIGHT, "{width}x{height} is too large");
+        self.width = width;
+        self.height = height;
+        self.planes = [[0; HIRES_SCREEN_HEIGHT]; PLANE_COUNT];
+    }
+
+    fn pixel(&self, plane: usize, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.planes[plane][y] & Self::pixel_mask(x) != 0
+    }
+
+    fn set_pixel(&mut self, plane: usize, x: usize, y: usize, lit: bool) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        if lit {
+            self.planes[plane][y] |= Self::pixel_mask(x);
+        } else {
+            self.planes[plane][y] &= !Self::pixel_mask(x);
+        }
+    }
+
+    fn clear(&mut self, plane: usize) {
+        self.planes[plane] = [0; HIRES_SCREEN_HEIGHT];
+    }
+
+    fn scroll(&mut self, plane: usize, dx: isize, dy: isize) {
+        let row_mask = self.row_mask();
+        let height = self.height;
+        let rows = &mut self.planes[plane][..height];
+
+        let distance = dy.unsigned_abs().min(height);
+        if dy > 0 {
+            rows.copy_within(0..height - distance, distance);
+            rows[..distance].fill(0);
+        } else {
+            rows.copy_within(distance..height, 0);
+            rows[height - distance..].fill(0);
+        }
+
+        let distance = dx.unsigned_abs() as u32;
+        for row in rows.iter_mut() {
+            let shifted = if dx > 0 { row.checked_shr(distance) } else { row.checked_shl(distance) };
+            *row = shifted.unwrap_or(0) & row_mask;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A display without any overridden methods, to test the provided implementations against `Bitmap`
+    struct PixelGrid {
+        width: usize,
+        height: usize,
+        pixels: Vec<Vec<bool>>,
+    }
+
+    impl Display for PixelGrid {
+        fn width(&self) -> usize {
+            self.width
+        }
+
+        fn height(&self) -> usize {
+            self.height
+        }
+
+        fn set_resolution(&mut self, width: usize, height: usize) {
+            self.width = width;
+            self.height = height;
+            self.pixels = vec![vec![false; width * height]; PLANE_COUNT];
+        }
+
+        fn pixel(&self, plane: usize, x: usize, y: usize) -> bool {
+            x < self.width && y < self.height && self.pixels[plane][y * self.width + x]
+        }
+
+        fn set_pixel(&mut self, plane: usize, x: usize, y: usize, lit: bool) {
+            if x < self.width && y < self.height {
+                self.pixels[plane][y * self.width + x] = lit;
+            }
+        }
+    }
+
+    /// Lights the same scattered pattern of pixels on `display`
+    fn draw_pattern(display: &mut dyn Display) {
+        for (x, y) in [(0, 0), (1, 0), (63, 0), (5, 7), (62, 31), (63, 31), (30, 16)] {
+            display.set_pixel(0, x, y, true);
+        }
+        display.set_pixel(1, 2, 2, true);
+    }
+
+    /// Gets every pixel of every plane of `display`
+    fn snapshot(display: &dyn Display) -> Vec<bool> {
+        (0..PLANE_COUNT)
+            .flat_map(|plane| {
+                (0..display.height()).flat_map(move |y| (0..display.width()).map(move |x| display.pixel(plane, x, y)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_set_resolution() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_pixel(0, 0, 0, true);
+
+        bitmap.set_resolution(HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+        assert_eq!((bitmap.width(), bitmap.height()), (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT));
+        assert!(!bitmap.pixel(0, 0, 0));
+    }
+
+    #[test]
+    fn test_pixel() {
+        let mut bitmap = Bitmap::new();
+
+        bitmap.set_pixel(1, 63, 31, true);
+        bitmap.set_pixel(0, 64, 0, true);
+        assert!(bitmap.pixel(1, 63, 31));
+        assert!(!bitmap.pixel(0, 63, 31));
+        assert!(!bitmap.pixel(0, 64, 0));
+        assert_eq!(bitmap.planes_at(63, 31), 0b10);
+
+        assert!(bitmap.toggle_pixel(1, 63, 31));
+        assert!(!bitmap.toggle_pixel(1, 63, 31));
+        assert!(bitmap.pixel(1, 63, 31));
+
+        bitmap.clear(1);
+        assert!(!bitmap.pixel(1, 63, 31));
+    }
+
+    #[test]
+    fn test_scroll_matches_provided_implementation() {
+        for (dx, dy) in [(0, 1), (0, 4), (0, -3), (4, 0), (-4, 0), (3, -2), (0, 40), (-70, 0)] {
+            let mut bitmap = Bitmap::new();
+            let mut grid = PixelGrid { width: 0, height: 0, pixels: Vec::new() };
+            grid.set_resolution(SCREEN_WIDTH, SCREEN_HEIGHT);
+            draw_pattern(&mut bitmap);
+            draw_pattern(&mut grid);
+
+            bitmap.scroll(0, dx, dy);
+            grid.scroll(0, dx, dy);
+            assert_eq!(snapshot(&bitmap), snapshot(&grid), "scrolling by {dx}, {dy}");
+        }
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut bitmap = Bitmap::new();
+        bitmap.set_pixel(0, 59, 0, true);
+        bitmap.set_pixel(0, 60, 0, true);
+        bitmap.set_pixel(1, 60, 0, true);
+
+        bitmap.scroll(0, 4, 1);
+        assert_eq!(bitmap.planes[0][0], 0);
+        assert_eq!(bitmap.planes[0][1], Bitmap::pixel_mask(63));
+        assert!(bitmap.pixel(1, 60, 0));
+    }
+}
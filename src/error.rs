@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::fmt;
+
+/// An error raised by the processor while emulating a cycle
+/// Every variant carries the program counter and opcode of the faulting instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip8Error {
+    /// A subroutine call was made with a full stack
+    StackOverflow { pc: usize, opcode: u16 },
+    /// A subroutine return was made with an empty stack
+    StackUnderflow { pc: usize, opcode: u16 },
+    /// The opcode does not match any known instruction
+    UnknownOpcode { pc: usize, opcode: u16 },
+    /// The instruction accessed an address outside of memory
+    /// If the fault occurred while fetching, `opcode` holds the bytes that could be read with the rest as zero
+    MemoryOutOfBounds { pc: usize, opcode: u16, address: usize },
+}
+
+impl Chip8Error {
+    /// Gets the program counter of the faulting instruction
+    pub fn pc(&self) -> usize {
+        match *self {
+            Chip8Error::StackOverflow { pc, .. }
+            | Chip8Error::StackUnderflow { pc, .. }
+            | Chip8Error::UnknownOpcode { pc, .. }
+            | Chip8Error::MemoryOutOfBounds { pc, .. } => pc,
+        }
+    }
+
+    /// Gets the opcode of the faulting instruction
+    pub fn opcode(&self) -> u16 {
+        match *self {
+            Chip8Error::StackOverflow { opcode, .. }
+            | Chip8Error::StackUnderflow { opcode, .. }
+            | Chip8Error::UnknownOpcode { opcode, .. }
+            | Chip8Error::MemoryOutOfBounds { opcode, .. } => opcode,
+        }
+    }
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Chip8Error::StackOverflow { .. } => write!(f, "stack overflow")?,
+            Chip8Error::StackUnderflow { .. } => write!(f, "stack underflow")?,
+            Chip8Error::UnknownOpcode { .. } => write!(f, "unknown opcode")?,
+            Chip8Error::MemoryOutOfBounds { address, .. } => {
+                write!(f, "memory access out of bounds at {address:#06X}")?
+            }
+        }
+        write!(f, " (opcode {:04X} at {:#05X})", self.opcode(), self.pc())
+    }
+}
+
+impl Error for Chip8Error {}
+
+/// An error raised while loading a program into memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    /// The program is larger than the memory available after its load address
+    TooLarge { size: usize, capacity: usize },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LoadError::TooLarge { size, capacity } => write!(
+                f,
+                "program is {size} bytes but only {capacity} bytes of memory are available at the load address"
+            ),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// An error raised while restoring a save state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// The data does not start with the save state header
+    NotAState,
+    /// The state was written by a version of the format that cannot be read
+    UnsupportedVersion(u16),
+    /// The checksum does not match the contents, so the state is corrupt
+    ChecksumMismatch,
+    /// The contents end early, run on past the end or hold a value no processor could have
+    Malformed,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StateError::NotAState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => write!(f, "save state version {version} is not supported"),
+            StateError::ChecksumMismatch => write!(f, "save state is corrupt (checksum mismatch)"),
+            StateError::Malformed => write!(f, "save state is malformed"),
+        }
+    }
+}
+
+impl Error for StateError {}
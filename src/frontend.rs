@@ -0,0 +1,384 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use sdl2::audio::{AudioCallback, AudioSpecDesired};
+use sdl2::event::Event;
+use sdl2::keyboard::{Mod, Scancode};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, WindowCanvas};
+
+use chip8::display::PLANE_COLORS;
+use chip8::megachip::DigitizedSound;
+use chip8::{AUDIO_PATTERN_SIZE, Chip8, CycleOutcome, Rewind, SCREEN_HEIGHT, SCREEN_WIDTH, TIMER_FREQUENCY};
+
+use crate::cli;
+
+/// The CHIP-8X foreground colors, indexed by `Chip8::zone_color`
+const CHIP_8X_FOREGROUND_COLORS: [Color; 8] = [
+    Color::RGB(0x00, 0x00, 0x00),
+    Color::RGB(0xFF, 0x00, 0x00),
+    Color::RGB(0x00, 0x00, 0xFF),
+    Color::RGB(0xFF, 0x00, 0xFF),
+    Color::RGB(0x00, 0xFF, 0x00),
+    Color::RGB(0xFF, 0xFF, 0x00),
+    Color::RGB(0x00, 0xFF, 0xFF),
+    Color::RGB(0xFF, 0xFF, 0xFF),
+];
+/// The CHIP-8X background colors, indexed by `Chip8::background_color`
+const CHIP_8X_BACKGROUND_COLORS: [Color; 4] = [
+    Color::RGB(0x00, 0x00, 0x80),
+    Color::RGB(0x00, 0x00, 0x00),
+    Color::RGB(0x00, 0x80, 0x00),
+    Color::RGB(0x80, 0x00, 0x00),
+];
+/// The frequency of the buzzer tone in hertz
+const TONE_FREQUENCY: f32 = 440.0;
+/// The amplitude of the buzzer tone
+const TONE_VOLUME: f32 = 0.1;
+/// The sample rate requested for audio playback
+const SAMPLE_RATE: i32 = 44100;
+/// How far back holding Backspace can rewind
+const REWIND_SECONDS: u32 = 60;
+/// The most memory the rewind history takes up, which only a MEGA-CHIP program redrawing its whole screen
+/// every frame comes close to within `REWIND_SECONDS`
+const REWIND_BYTE_LIMIT: usize = 512 << 20;
+
+/// The sound generator used for the buzzer
+/// Plays a MEGA-CHIP digitized sound if one is playing, and otherwise a square wave,
+/// or the XO-CHIP audio pattern if the program has loaded one
+struct Buzzer {
+    sample_rate: f32,
+    /// The audio pattern and its playback rate in bits per second
+    pattern: Option<([u8; AUDIO_PATTERN_SIZE], f32)>,
+    phase: f32,
+    volume: f32,
+    digitized_sound: Option<DigitizedSound>,
+    /// The position in `digitized_sound` in samples
+    sound_position: f32,
+}
+
+impl Buzzer {
+    /// Gets the next sample of the digitized sound, or `None` if there is none or it has finished
+    fn next_digitized_sample(&mut self) -> Option<f32> {
+        let sound = self.digitized_sound.as_ref()?;
+        let mut index = self.sound_position as usize;
+        if index >= sound.samples.len() {
+            if !sound.looping || sound.samples.is_empty() {
+                self.digitized_sound = None;
+                return None;
+            }
+            self.sound_position = 0.0;
+            index = 0;
+        }
+
+        self.sound_position += sound.sample_rate as f32 / self.sample_rate;
+        Some((sound.samples[index] as f32 - 128.0) / 128.0 * self.volume)
+    }
+}
+
+impl AudioCallback for Buzzer {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            if let Some(digitized_sample) = self.next_digitized_sample() {
+                *sample = digitized_sample;
+                continue;
+            }
+
+            let (high, cycle_frequency) = match &self.pattern {
+                Some((pattern, bit_rate)) => {
+                    let bit = (self.phase * (AUDIO_PATTERN_SIZE * 8) as f32) as usize;
+                    let high = (pattern[bit / 8] >> (7 - bit % 8)) & 0x01 != 0;
+                    (high, bit_rate / (AUDIO_PATTERN_SIZE * 8) as f32)
+                }
+                None => (self.phase < 0.5, TONE_FREQUENCY),
+            };
+
+            *sample = if high { self.volume } else { -self.volume };
+            self.phase = (self.phase + cycle_frequency / self.sample_rate) % 1.0;
+        }
+    }
+}
+
+/// Maps a physical key on the left of a QWERTY keyboard to the matching key on the hexadecimal keypad
+/// ```text
+/// 1 2 3 4      1 2 3 C
+/// Q W E R  ->  4 5 6 D
+/// A S D F      7 8 9 E
+/// Z X C V      A 0 B F
+/// ```
+fn keypad_key(scancode: Scancode) -> Option<u8> {
+    match scancode {
+        Scancode::Num1 => Some(0x1),
+        Scancode::Num2 => Some(0x2),
+        Scancode::Num3 => Some(0x3),
+        Scancode::Num4 => Some(0xC),
+        Scancode::Q => Some(0x4),
+        Scancode::W => Some(0x5),
+        Scancode::E => Some(0x6),
+        Scancode::R => Some(0xD),
+        Scancode::A => Some(0x7),
+        Scancode::S => Some(0x8),
+        Scancode::D => Some(0x9),
+        Scancode::F => Some(0xE),
+        Scancode::Z => Some(0xA),
+        Scancode::X => Some(0x0),
+        Scancode::C => Some(0xB),
+        Scancode::V => Some(0xF),
+        _ => None,
+    }
+}
+
+/// Maps a key on the numeric keypad to the matching key on the second hexadecimal keypad of CHIP-8X
+/// ```text
+/// 7 8 9 /          1 2 3 C
+/// 4 5 6 *      ->  4 5 6 D
+/// 1 2 3 -          7 8 9 E
+/// 0 . Enter +      A 0 B F
+/// ```
+fn second_keypad_key(scancode: Scancode) -> Option<u8> {
+    match scancode {
+        Scancode::Kp7 => Some(0x1),
+        Scancode::Kp8 => Some(0x2),
+        Scancode::Kp9 => Some(0x3),
+        Scancode::KpDivide => Some(0xC),
+        Scancode::Kp4 => Some(0x4),
+        Scancode::Kp5 => Some(0x5),
+        Scancode::Kp6 => Some(0x6),
+        Scancode::KpMultiply => Some(0xD),
+        Scancode::Kp1 => Some(0x7),
+        Scancode::Kp2 => Some(0x8),
+        Scancode::Kp3 => Some(0x9),
+        Scancode::KpMinus => Some(0xE),
+        Scancode::Kp0 => Some(0xA),
+        Scancode::KpPeriod => Some(0x0),
+        Scancode::KpEnter => Some(0xB),
+        Scancode::KpPlus => Some(0xF),
+        _ => None,
+    }
+}
+
+/// Maps a function key to the quick-save slot it saves to with Shift held and loads from otherwise
+fn state_slot(scancode: Scancode) -> Option<usize> {
+    match scancode {
+        Scancode::F1 => Some(1),
+        Scancode::F2 => Some(2),
+        Scancode::F3 => Some(3),
+        Scancode::F4 => Some(4),
+        _ => None,
+    }
+}
+
+/// Saves `processor` to quick-save `slot` next to the program at `rom_path`
+/// Failures are reported without stopping the emulator
+fn quick_save(processor: &Chip8, rom_path: &Path, slot: usize) {
+    let path = cli::slot_path(rom_path, slot);
+    match fs::write(&path, processor.save_state()) {
+        Ok(()) => println!("saved slot {slot} to '{}'", path.display()),
+        Err(error) => eprintln!("error: could not write '{}': {error}", path.display()),
+    }
+}
+
+/// Restores `processor` from quick-save `slot` next to the program at `rom_path` and returns whether it succeeded
+/// Failures are reported without stopping the emulator and leave `processor` untouched
+fn quick_load(processor: &mut Chip8, rom_path: &Path, slot: usize) -> bool {
+    let path = cli::slot_path(rom_path, slot);
+    let result = fs::read(&path)
+        .map_err(|error| error.to_string())
+        .and_then(|state| processor.load_state(&state).map_err(|error| error.to_string()));
+    match result {
+        Ok(()) => {
+            println!("loaded slot {slot} from '{}'", path.display());
+            true
+        }
+        Err(error) => {
+            eprintln!("error: could not restore '{}': {error}", path.display());
+            false
+        }
+    }
+}
+
+/// Runs `processor` in a window where each low resolution pixel is drawn as a `scale` by `scale` square
+/// F1 to F4 load the quick-save slots of the program at `rom_path`, and Shift with F1 to F4 saves to them
+/// Holding Backspace plays the last `REWIND_SECONDS` backwards
+/// Returns when the window is closed or Escape is pressed
+pub fn run(processor: &mut Chip8, rom_path: &Path, instructions_per_frame: usize, scale: u32) -> Result<(), String> {
+    let context = sdl2::init()?;
+    let video = context.video()?;
+    let audio = context.audio()?;
+
+    let window = video
+        .window("CHIP-8", SCREEN_WIDTH as u32 * scale, SCREEN_HEIGHT as u32 * scale)
+        .position_centered()
+        .build()
+        .map_err(|error| error.to_string())?;
+    let mut canvas = window.into_canvas().build().map_err(|error| error.to_string())?;
+
+    let desired_spec = AudioSpecDesired {
+        freq: Some(SAMPLE_RATE),
+        channels: Some(1),
+        samples: None,
+    };
+    let mut buzzer = audio.open_playback(None, &desired_spec, |spec| Buzzer {
+        sample_rate: spec.freq as f32,
+        pattern: None,
+        phase: 0.0,
+        volume: TONE_VOLUME,
+        digitized_sound: None,
+        sound_position: 0.0,
+    })?;
+
+    let mut event_pump = context.event_pump()?;
+    let frame_duration = Duration::from_secs(1) / TIMER_FREQUENCY;
+    let mut next_frame = Instant::now();
+    let mut halted = false;
+    let mut rewind = Rewind::with_byte_limit((REWIND_SECONDS * TIMER_FREQUENCY) as usize, REWIND_BYTE_LIMIT);
+    let mut rewinding = false;
+
+    draw(&mut canvas, processor)?;
+
+    loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    scancode: Some(Scancode::Escape),
+                    ..
+                } => return Ok(()),
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    keymod,
+                    repeat: false,
+                    ..
+                } => {
+                    rewinding |= scancode == Scancode::Backspace;
+                    if let Some(slot) = state_slot(scancode) {
+                        if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+                            quick_save(processor, rom_path, slot);
+                        } else if quick_load(processor, rom_path, slot) {
+                            halted = false;
+                        }
+                    }
+                    if let Some(key) = keypad_key(scancode) {
+                        processor.press_key(key);
+                    }
+                    if let Some(key) = second_keypad_key(scancode) {
+                        processor.press_second_key(key);
+                    }
+                }
+                Event::KeyUp {
+                    scancode: Some(scancode),
+                    ..
+                } => {
+                    rewinding &= scancode != Scancode::Backspace;
+                    if let Some(key) = keypad_key(scancode) {
+                        processor.release_key(key);
+                    }
+                    if let Some(key) = second_keypad_key(scancode) {
+                        processor.release_second_key(key);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if rewinding {
+            if let Some(state) = rewind.step_back() {
+                processor.load_state(state).map_err(|error| error.to_string())?;
+                halted = false;
+                if rewind.is_empty() {
+                    println!("rewound to the start of the history");
+                }
+            }
+        } else if !halted {
+            let outcome = processor.run_frame(instructions_per_frame).map_err(|error| error.to_string())?;
+            halted = outcome == CycleOutcome::Halted;
+            rewind.record(processor.save_state());
+        }
+
+        if processor.take_sound_changed() {
+            let mut buzzer = buzzer.lock();
+            buzzer.digitized_sound = processor.digitized_sound().cloned();
+            buzzer.sound_position = 0.0;
+        }
+
+        let digitized_sound_playing = buzzer.lock().digitized_sound.is_some();
+        if processor.is_sound_playing() || digitized_sound_playing {
+            buzzer.lock().pattern = processor
+                .audio_pattern()
+                .map(|pattern| (*pattern, processor.audio_sample_rate()));
+            buzzer.resume();
+        } else {
+            buzzer.pause();
+        }
+
+        if processor.take_draw_flag() {
+            draw(&mut canvas, processor)?;
+        }
+
+        next_frame += frame_duration;
+        let now = Instant::now();
+        if next_frame > now {
+            thread::sleep(next_frame - now);
+        } else {
+            next_frame = now;
+        }
+    }
+}
+
+/// Draws the screen of `processor` to `canvas` and presents it
+/// The screen is scaled to fit the window, keeping its aspect ratio, so high resolution pixels are drawn at half the size
+/// and the 64x64 screen of hi-res CHIP-8 is centered between black bars
+/// CHIP-8X programs are drawn with their color overlay instead of the plane colors
+fn draw(canvas: &mut WindowCanvas, processor: &Chip8) -> Result<(), String> {
+    let width = processor.screen_width();
+    let height = processor.screen_height();
+    canvas.set_logical_size(width as u32, height as u32).map_err(|error| error.to_string())?;
+
+    if !processor.variant().has_chip8x_instructions() || processor.is_megachip_mode() {
+        return draw_rgba(canvas, processor);
+    }
+
+    canvas.set_draw_color(CHIP_8X_BACKGROUND_COLORS[processor.background_color() as usize]);
+    canvas.clear();
+
+    let mut lit_pixels = vec![Vec::new(); CHIP_8X_FOREGROUND_COLORS.len()];
+    for y in 0..height {
+        for x in 0..width {
+            if processor.pixel_planes(x, y) != 0 {
+                lit_pixels[processor.zone_color(x, y) as usize].push(Rect::new(x as i32, y as i32, 1, 1));
+            }
+        }
+    }
+
+    for (&color, rects) in CHIP_8X_FOREGROUND_COLORS.iter().zip(&lit_pixels) {
+        canvas.set_draw_color(color);
+        canvas.fill_rects(rects)?;
+    }
+    canvas.present();
+    Ok(())
+}
+
+/// Draws the screen of `processor` to `canvas` as an RGBA texture in the plane colors, or the MEGA-CHIP colors with their opacity, and presents it
+fn draw_rgba(canvas: &mut WindowCanvas, processor: &Chip8) -> Result<(), String> {
+    let width = processor.screen_width() as u32;
+    let height = processor.screen_height() as u32;
+    let texture_creator = canvas.texture_creator();
+    let mut texture = texture_creator
+        .create_texture_streaming(PixelFormatEnum::RGBA32, width, height)
+        .map_err(|error| error.to_string())?;
+
+    let bytes = processor.to_rgba(&PLANE_COLORS);
+    texture.update(None, &bytes, width as usize * 4).map_err(|error| error.to_string())?;
+    texture.set_blend_mode(BlendMode::Blend);
+
+    canvas.set_draw_color(Color::BLACK);
+    canvas.clear();
+    canvas.copy(&texture, None, None)?;
+    canvas.present();
+    Ok(())
+}
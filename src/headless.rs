@@ -0,0 +1,423 @@
+use std::fmt::Write;
+
+use crate::chip8::{Chip8, CycleOutcome, REGISTER_COUNT, TIMER_FREQUENCY};
+use crate::display::PLANE_COLORS;
+use crate::error::Chip8Error;
+use crate::state::crc32;
+
+/// The characters ASCII art shows each pixel as, indexed by the bits of `Chip8::pixel_planes`
+const ASCII_PIXELS: [char; 4] = ['.', '#', '+', '*'];
+/// The bytes every PNG file starts with
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// The most bytes a stored deflate block can hold
+const MAX_STORED_BLOCK_SIZE: usize = 0xFFFF;
+/// The modulus of both sums of `adler32`
+const ADLER32_MODULUS: u32 = 65521;
+
+/// The point at which a headless run stops, unless the program halts first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Stop after this many frames
+    Frames(u64),
+    /// Stop after this many instructions, ending the frame early if needed
+    Cycles(u64),
+}
+
+/// The limit of a run that isn't given one, ten minutes of emulated time, so that a program which never halts
+/// can't run forever
+pub const DEFAULT_LIMIT: Limit = Limit::Frames(10 * 60 * TIMER_FREQUENCY as u64);
+
+/// A key pressed or released by a script at the start of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// The number of frames run before the key changes, so 0 is before the first frame
+    pub frame: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+/// Runs a program without a window as fast as possible, pressing keys from a script
+pub struct HeadlessRunner {
+    instructions_per_frame: usize,
+    /// The point to stop at if the program hasn't halted by then
+    limit: Limit,
+    /// The scripted key events in order of frame
+    keys: Vec<KeyEvent>,
+    /// The index in `keys` of the next event to apply
+    next_key: usize,
+    frames: u64,
+    cycles: u64,
+}
+
+impl HeadlessRunner {
+    /// Creates a runner that emulates `instructions_per_frame` instructions per frame until `limit`,
+    /// applying `keys` at the start of their frames
+    pub fn new(instructions_per_frame: usize, limit: Limit, mut keys: Vec<KeyEvent>) -> Self {
+        keys.sort_by_key(|event| event.frame);
+        HeadlessRunner {
+            instructions_per_frame,
+            limit,
+            keys,
+            next_key: 0,
+            frames: 0,
+            cycles: 0,
+        }
+    }
+
+    /// Gets the number of frames run so far
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Gets the number of instructions emulated so far, not counting a faulting one
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Runs `processor` until the limit is reached, the program halts or it hits a watchpoint that stops execution,
+    /// and returns the last outcome
+    /// Timers tick at the end of every frame, including one cut short by a cycle limit
+    pub fn run(&mut self, processor: &mut Chip8) -> Result<CycleOutcome, Chip8Error> {
+        let mut outcome = CycleOutcome::Executed;
+        loop {
+            let budget = match self.limit {
+                Limit::Frames(frames) if self.frames >= frames => return Ok(outcome),
+                Limit::Cycles(cycles) if self.cycles >= cycles => return Ok(outcome),
+                Limit::Cycles(cycles) => {
+                    self.instructions_per_frame.min((cycles - self.cycles).try_into().unwrap_or(usize::MAX))
+                }
+                Limit::Frames(_) => self.instructions_per_frame,
+            };
+
+            self.apply_keys(processor);
+            let executed;
+            (outcome, executed) = processor.run_cycles(budget)?;
+            processor.tick_timers();
+            self.cycles += executed as u64;
+            self.frames += 1;
+
+            if matches!(outcome, CycleOutcome::Halted | CycleOutcome::Watchpoint) {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    /// Applies the scripted key events for the frame about to run
+    fn apply_keys(&mut self, processor: &mut Chip8) {
+        while let Some(event) = self.keys.get(self.next_key).filter(|event| event.frame <= self.frames) {
+            if event.pressed {
+                processor.press_key(event.key);
+            } else {
+                processor.release_key(event.key);
+            }
+            self.next_key += 1;
+        }
+    }
+}
+
+/// The file formats the screen can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenFormat {
+    /// A PNG image in the colors of `PLANE_COLORS`, or in color in MEGA-CHIP mode
+    Png,
+    /// A binary PBM image with lit pixels in black
+    Pbm,
+    /// Text with a character from `ASCII_PIXELS` for each pixel
+    Ascii,
+}
+
+impl ScreenFormat {
+    /// The file extensions accepted by `ScreenFormat::from_extension`
+    pub const EXTENSIONS: [&str; 3] = ["png", "pbm", "txt"];
+
+    /// Looks up the format of files with the extension `extension`
+    pub fn from_extension(extension: &str) -> Option<ScreenFormat> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(ScreenFormat::Png),
+            "pbm" => Some(ScreenFormat::Pbm),
+            "txt" => Some(ScreenFormat::Ascii),
+            _ => None,
+        }
+    }
+
+    /// Encodes the screen of `processor` as the contents of a file in the format
+    pub fn encode(&self, processor: &Chip8) -> Vec<u8> {
+        let width = processor.screen_width();
+        let height = processor.screen_height();
+        match self {
+            ScreenFormat::Png => encode_png(width, height, &processor.to_rgba(&PLANE_COLORS)),
+            ScreenFormat::Pbm => {
+                let mut bytes = format!("P4\n{width} {height}\n").into_bytes();
+                for y in 0..height {
+                    let lit: Vec<bool> = (0..width).map(|x| processor.pixel(x, y)).collect();
+                    bytes.extend(lit.chunks(8).map(|pixels| {
+                        pixels.iter().enumerate().fold(0u8, |byte, (bit, &lit)| byte | (lit as u8) << (7 - bit))
+                    }));
+                }
+                bytes
+            }
+            ScreenFormat::Ascii => {
+                let mut text = String::with_capacity((width + 1) * height);
+                for y in 0..height {
+                    text.extend((0..width).map(|x| ASCII_PIXELS[processor.pixel_planes(x, y) as usize]));
+                    text.push('\n');
+                }
+                text.into_bytes()
+            }
+        }
+    }
+}
+
+/// Formats the program counter, index register, timers, general purpose registers and stack of `processor`
+pub fn dump_registers(processor: &Chip8) -> String {
+    let mut dump = format!(
+        "PC {:#06X}  I {:#06X}  DT {:#04X}  ST {:#04X}\n",
+        processor.program_counter(),
+        processor.index_register(),
+        processor.delay_timer(),
+        processor.sound_timer()
+    );
+    let registers: Vec<String> =
+        processor.registers().iter().enumerate().map(|(index, value)| format!("V{index:X} {value:#04X}")).collect();
+    for row in registers.chunks(REGISTER_COUNT / 2) {
+        writeln!(dump, "{}", row.join("  ")).unwrap();
+    }
+    let stack: Vec<String> = processor.stack().iter().map(|address| format!("{address:#06X}")).collect();
+    writeln!(dump, "Stack [{}]", stack.join(", ")).unwrap();
+    dump
+}
+
+/// Encodes `width` by `height` pixels of RGBA bytes as an uncompressed PNG image
+fn encode_png(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel of RGBA, deflate, adaptive filtering and no interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    // Every row starts with filter type 0, which leaves it unfiltered
+    let mut scanlines = Vec::with_capacity(rgba.len() + height);
+    for row in rgba.chunks(width * 4) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_png_chunk(&mut png, b"IHDR", &header);
+    write_png_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+    write_png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Appends a PNG chunk with its length in front and the CRC-32 of its type and data at the end
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let checksum = crc32(&png[start..]);
+    png.extend_from_slice(&checksum.to_be_bytes());
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32 KiB window and no preset dictionary, with the check bits making the header a multiple of 31
+    let mut stream = vec![0x78, 0x01];
+    let block_count = data.len().div_ceil(MAX_STORED_BLOCK_SIZE).max(1);
+    for (index, block) in data.chunks(MAX_STORED_BLOCK_SIZE).chain(data.is_empty().then_some(&[][..])).enumerate() {
+        stream.push((index + 1 == block_count) as u8);
+        stream.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        stream.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// Computes the Adler-32 checksum of `data`, as used by zlib
+fn adler32(data: &[u8]) -> u32 {
+    let (low, high) = data.iter().fold((1, 0), |(low, high), &byte| {
+        let low = (low + byte as u32) % ADLER32_MODULUS;
+        (low, (high + low) % ADLER32_MODULUS)
+    });
+    (high << 16) | low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quirks::Quirks;
+    use crate::watch::Watchpoint;
+
+    /// Creates a processor running `program` with `Quirks::MODERN`
+    fn processor(program: &[u8]) -> Chip8 {
+        let mut processor = Chip8::new(Quirks::MODERN);
+        processor.load(program).unwrap();
+        processor
+    }
+
+    #[test]
+    fn test_run_frames() {
+        // Count frames in V0, then halt once V0 reaches 3
+        let mut processor = processor(&[0x70, 0x01, 0x30, 0x03, 0x12, 0x00, 0x12, 0x06]);
+        let mut runner = HeadlessRunner::new(2, Limit::Frames(2), Vec::new());
+        assert_eq!(runner.run(&mut processor), Ok(CycleOutcome::Executed));
+        assert_eq!((runner.frames(), runner.cycles()), (2, 4));
+        assert_eq!(processor.registers()[0x0], 0x02);
+
+        let mut runner = HeadlessRunner::new(2, DEFAULT_LIMIT, Vec::new());
+        assert_eq!(runner.run(&mut processor), Ok(CycleOutcome::Halted));
+        assert_eq!(processor.registers()[0x0], 0x03);
+    }
+
+    #[test]
+    fn test_run_default_limit() {
+        // Count in V0 forever
+        let mut processor = processor(&[0x70, 0x01, 0x12, 0x00]);
+        let mut runner = HeadlessRunner::new(2, DEFAULT_LIMIT, Vec::new());
+        assert_eq!(runner.run(&mut processor), Ok(CycleOutcome::Executed));
+        assert_eq!(Limit::Frames(runner.frames()), DEFAULT_LIMIT);
+    }
+
+    #[test]
+    fn test_run_watchpoint() {
+        // Store an increasing V0 at 0x300 forever
+        let mut processor = processor(&[0xA3, 0x00, 0x70, 0x01, 0xF0, 0x55, 0x12, 0x02]);
+        processor.add_watchpoint(Watchpoint { addresses: 0x300..=0x300, reads: false, writes: true, stops: true });
+        let mut runner = HeadlessRunner::new(100, Limit::Frames(5), Vec::new());
+        assert_eq!(runner.run(&mut processor), Ok(CycleOutcome::Watchpoint));
+        assert_eq!((runner.frames(), runner.cycles()), (1, 3));
+    }
+
+    #[test]
+    fn test_run_cycles() {
+        // Set the delay timer, then loop forever
+        let mut processor = processor(&[0x60, 0x10, 0xF0, 0x15, 0x70, 0x01, 0x12, 0x04]);
+        let mut runner = HeadlessRunner::new(4, Limit::Cycles(10), Vec::new());
+        assert_eq!(runner.run(&mut processor), Ok(CycleOutcome::Executed));
+        assert_eq!((runner.frames(), runner.cycles()), (3, 10));
+        assert_eq!(processor.registers()[0x0], 0x14);
+        assert_eq!(processor.delay_timer(), 0x0D);
+    }
+
+    #[test]
+    fn test_run_keys() {
+        // Wait for a key, store it in V1 and halt
+        let mut processor = processor(&[0xF1, 0x0A, 0x12, 0x02]);
+        let keys = vec![
+            KeyEvent { frame: 5, key: 0xA, pressed: false },
+            KeyEvent { frame: 3, key: 0xA, pressed: true },
+        ];
+        let mut runner = HeadlessRunner::new(10, Limit::Frames(20), keys);
+        assert_eq!(runner.run(&mut processor), Ok(CycleOutcome::Halted));
+        assert_eq!(runner.frames(), 6);
+        assert_eq!(processor.registers()[0x1], 0xA);
+        assert!(!processor.is_key_pressed(0xA));
+    }
+
+    #[test]
+    fn test_run_key_tap() {
+        // A key pressed and released in the same frame is seen by FX0A
+        let mut processor = processor(&[0xF1, 0x0A, 0x12, 0x02]);
+        let keys = vec![
+            KeyEvent { frame: 2, key: 0x7, pressed: true },
+            KeyEvent { frame: 2, key: 0x7, pressed: false },
+        ];
+        let mut runner = HeadlessRunner::new(10, Limit::Frames(20), keys);
+        assert_eq!(runner.run(&mut processor), Ok(CycleOutcome::Halted));
+        assert_eq!(processor.registers()[0x1], 0x7);
+    }
+
+    #[test]
+    fn test_run_error() {
+        let mut processor = processor(&[0x00, 0xEE]);
+        let mut runner = HeadlessRunner::new(10, DEFAULT_LIMIT, Vec::new());
+        assert_eq!(runner.run(&mut processor), Err(Chip8Error::StackUnderflow { pc: 0x200, opcode: 0x00EE }));
+    }
+
+    #[test]
+    fn test_from_extension() {
+        for extension in ScreenFormat::EXTENSIONS {
+            assert!(ScreenFormat::from_extension(extension).is_some());
+        }
+        assert_eq!(ScreenFormat::from_extension("PNG"), Some(ScreenFormat::Png));
+        assert_eq!(ScreenFormat::from_extension("bmp"), None);
+    }
+
+    #[test]
+    fn test_encode_ascii() {
+        // Draw the top row of the font character 0
+        let mut processor = processor(&[0xF0, 0x29, 0xD0, 0x01, 0x12, 0x04]);
+        HeadlessRunner::new(10, DEFAULT_LIMIT, Vec::new()).run(&mut processor).unwrap();
+
+        let text = String::from_utf8(ScreenFormat::Ascii.encode(&processor)).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 32);
+        assert_eq!(lines[0], format!("####{}", ".".repeat(60)));
+        assert_eq!(lines[1], ".".repeat(64));
+    }
+
+    #[test]
+    fn test_encode_pbm() {
+        let mut processor = processor(&[0xF0, 0x29, 0xD0, 0x01, 0x12, 0x04]);
+        HeadlessRunner::new(10, DEFAULT_LIMIT, Vec::new()).run(&mut processor).unwrap();
+
+        let pbm = ScreenFormat::Pbm.encode(&processor);
+        let header = b"P4\n64 32\n";
+        assert_eq!(&pbm[..header.len()], header);
+        assert_eq!(pbm.len(), header.len() + 8 * 32);
+        assert_eq!(pbm[header.len()], 0xF0);
+        assert!(pbm[header.len() + 1..].iter().all(|&byte| byte == 0x00));
+    }
+
+    #[test]
+    fn test_encode_png() {
+        let rgba = [0x11; 3 * 2 * 4];
+        let png = encode_png(3, 2, &rgba);
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        assert_eq!(&png[8..16], &[0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(&png[16..29], &[0, 0, 0, 3, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+        assert_eq!(png[29..33], crc32(&png[12..29]).to_be_bytes());
+
+        let scanlines: Vec<u8> = [[0x00].as_slice(), &[0x11; 12], &[0x00], &[0x11; 12]].concat();
+        let idat = &png[33..];
+        assert_eq!(idat[..8], [0, 0, 0, 37, b'I', b'D', b'A', b'T']);
+        assert_eq!(idat[8..15], [0x78, 0x01, 0x01, 26, 0, !26, 0xFF]);
+        assert_eq!(idat[15..41], scanlines);
+        assert_eq!(idat[41..45], adler32(&scanlines).to_be_bytes());
+        assert_eq!(&png[png.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+    }
+
+    #[test]
+    fn test_zlib_stored() {
+        let data = vec![0x5A; MAX_STORED_BLOCK_SIZE + 1];
+        let stream = zlib_stored(&data);
+        assert_eq!(stream[2..7], [0x00, 0xFF, 0xFF, 0x00, 0x00]);
+        let second = 7 + MAX_STORED_BLOCK_SIZE;
+        assert_eq!(stream[second..second + 6], [0x01, 0x01, 0x00, 0xFE, 0xFF, 0x5A]);
+        assert_eq!(stream.len(), second + 6 + 4);
+
+        assert_eq!(zlib_stored(&[]), [0x78, 0x01, 0x01, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 0x0000_0001);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_dump_registers() {
+        // Call a subroutine that sets V0, VB and I
+        let mut processor = processor(&[0x22, 0x04, 0x00, 0x00, 0x60, 0x12, 0x6B, 0xFF, 0xA1, 0x23, 0x12, 0x0A]);
+        HeadlessRunner::new(10, DEFAULT_LIMIT, Vec::new()).run(&mut processor).unwrap();
+
+        assert_eq!(
+            dump_registers(&processor),
+            "PC 0x020A  I 0x0123  DT 0x00  ST 0x00\n\
+             V0 0x12  V1 0x00  V2 0x00  V3 0x00  V4 0x00  V5 0x00  V6 0x00  V7 0x00\n\
+             V8 0x00  V9 0x00  VA 0x00  VB 0xFF  VC 0x00  VD 0x00  VE 0x00  VF 0x00\n\
+             Stack [0x0200]\n"
+        );
+    }
+}
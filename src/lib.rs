@@ -0,0 +1,47 @@
+//! An emulator core for CHIP-8 and its descendants: hi-res CHIP-8, CHIP-8X, SUPER-CHIP, XO-CHIP and MEGA-CHIP 8
+//!
+//! The core has no window, sound or input of its own. A frontend creates a `Chip8`, loads a program,
+//! calls `Chip8::run_frame` at `TIMER_FREQUENCY`, forwards key presses and draws the screen,
+//! either through `Chip8::to_rgba` or a `Display` of its own.
+//!
+//! ```
+//! use chip8::{Chip8, CycleOutcome, Quirks};
+//!
+//! let mut processor = Chip8::new(Quirks::MODERN);
+//! // Draw the font character for V0 at the top left, then loop forever
+//! processor.load(&[0xF0, 0x29, 0xD0, 0x05, 0x12, 0x04]).unwrap();
+//!
+//! assert_eq!(processor.run_frame(10), Ok(CycleOutcome::Halted));
+//! assert!(processor.pixel(0, 0));
+//! assert_eq!(processor.program_counter(), 0x204);
+//! ```
+
+mod chip8;
+pub mod debugger;
+pub mod display;
+pub mod error;
+pub mod font;
+pub mod headless;
+pub mod megachip;
+pub mod quirks;
+pub mod random;
+pub mod rewind;
+mod state;
+pub mod variant;
+pub mod watch;
+
+pub use crate::chip8::{
+    AUDIO_PATTERN_SIZE, CHIP_8X_START_ADDRESS, COLOR_ZONE_COLUMNS, Chip8, Config, CycleOutcome, ETI_660_START_ADDRESS,
+    HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH, KEYPAD_SIZE, MEGA_CHIP_MEMORY_SIZE, MEGA_CHIP_SCREEN_HEIGHT,
+    MEGA_CHIP_SCREEN_WIDTH, MEMORY_SIZE, PLANE_COUNT, PROGRAM_START_ADDRESS, REGISTER_COUNT, SCREEN_HEIGHT,
+    SCREEN_WIDTH, TIMER_FREQUENCY, TWO_PAGE_SCREEN_HEIGHT, XO_CHIP_MEMORY_SIZE,
+};
+pub use crate::display::{Bitmap, Display};
+pub use crate::error::{Chip8Error, LoadError, StateError};
+pub use crate::font::FontSet;
+pub use crate::quirks::Quirks;
+pub use crate::random::{RandomAlgorithm, RandomSource};
+pub use crate::rewind::Rewind;
+pub use crate::state::STATE_VERSION;
+pub use crate::variant::Variant;
+pub use crate::watch::{Access, WatchHit, Watchpoint};
@@ -1,12 +1,124 @@
-use chip8::Chip8;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
 
-mod chip8;
+use chip8::debugger::Debugger;
+use chip8::headless::{self, HeadlessRunner};
+use chip8::{Chip8, Config, CycleOutcome, FontSet};
+use cli::{Command, Options, USAGE};
 
-fn main() {
-    let mut processor = Chip8::new();
-    processor.load(&[]);
+mod cli;
+#[cfg(feature = "sdl")]
+mod frontend;
 
+fn main() -> ExitCode {
+    let options = match cli::parse(env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {error}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Loads the program named in `options` and runs it until it exits
+fn run(options: &Options) -> Result<(), String> {
+    let rom = fs::read(&options.rom_path)
+        .map_err(|error| format!("could not read '{}': {error}", options.rom_path.display()))?;
+
+    let mut processor = Chip8::with_config(Config {
+        variant: options.variant,
+        quirks: options.quirks,
+        font: FontSet::default(),
+        random: options.random,
+        seed: options.seed,
+    });
+    processor
+        .load_at(&rom, options.start_address)
+        .map_err(|error| format!("could not load '{}': {error}", options.rom_path.display()))?;
+
+    if let Some(state_path) = &options.state_path {
+        let state = fs::read(state_path)
+            .map_err(|error| format!("could not read '{}': {error}", state_path.display()))?;
+        processor
+            .load_state(&state)
+            .map_err(|error| format!("could not restore '{}': {error}", state_path.display()))?;
+    }
+
+    if options.headless {
+        run_headless(&mut processor, options)
+    } else if options.debug {
+        run_debugger(&mut processor, options.instructions_per_frame)
+    } else {
+        run_windowed(&mut processor, options)
+    }
+}
+
+/// Runs `processor` as fast as possible without a window until the limit in `options` or until the program halts,
+/// then prints the registers and writes the screen, even if the program faulted
+/// Without a limit in `options` the run stops at `headless::DEFAULT_LIMIT`,
+/// which is an error if the program hasn't halted by then
+fn run_headless(processor: &mut Chip8, options: &Options) -> Result<(), String> {
+    let limit = options.limit.unwrap_or(headless::DEFAULT_LIMIT);
+    let mut runner = HeadlessRunner::new(options.instructions_per_frame, limit, options.keys.clone());
+    let result = runner.run(processor);
+
+    println!("Frames {}  Cycles {}", runner.frames(), runner.cycles());
+    print!("{}", headless::dump_registers(processor));
+    if let Some((path, format)) = &options.screen {
+        fs::write(path, format.encode(processor))
+            .map_err(|error| format!("could not write '{}': {error}", path.display()))?;
+    }
+    match result.map_err(|error| error.to_string())? {
+        CycleOutcome::Halted | CycleOutcome::Watchpoint => Ok(()),
+        _ if options.limit.is_some() => Ok(()),
+        _ => Err(format!(
+            "the program didn't halt within {} frames; pass '--frames' or '--cycles' to set where it stops",
+            runner.frames()
+        )),
+    }
+}
+
+/// Runs `processor` in a debugger that reads commands from standard input until it quits or the input ends
+fn run_debugger(processor: &mut Chip8, instructions_per_frame: usize) -> Result<(), String> {
+    let mut debugger = Debugger::new(instructions_per_frame);
+    println!("Type 'help' for a list of commands");
+    print!("{}", debugger.location(processor));
+
+    let mut lines = io::stdin().lock().lines();
     loop {
-        processor.emulate_cycle();
+        print!("(chip8) ");
+        io::stdout().flush().map_err(|error| error.to_string())?;
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        match debugger.execute(processor, &line.map_err(|error| error.to_string())?) {
+            Some(output) => print!("{output}"),
+            None => return Ok(()),
+        }
     }
 }
+
+#[cfg(feature = "sdl")]
+fn run_windowed(processor: &mut Chip8, options: &Options) -> Result<(), String> {
+    frontend::run(processor, &options.rom_path, options.instructions_per_frame, options.scale)
+}
+
+#[cfg(not(feature = "sdl"))]
+fn run_windowed(_processor: &mut Chip8, _options: &Options) -> Result<(), String> {
+    Err("this build has no window support; rebuild with '--features sdl' or pass '--headless'".to_string())
+}
@@ -0,0 +1,125 @@
+/// How MEGA-CHIP sprite pixels are combined with the pixels already on the screen, selected by 080N
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// The sprite pixel replaces the screen pixel
+    #[default]
+    Normal,
+    /// The sprite pixel is mixed in at 25% opacity
+    Alpha25,
+    /// The sprite pixel is mixed in at 50% opacity
+    Alpha50,
+    /// The color channels are added, saturating at full intensity
+    Additive,
+    /// The color channels are multiplied
+    Multiply,
+}
+
+impl BlendMode {
+    /// Looks up the blend mode selected by `n` in 080N
+    pub fn from_code(n: u8) -> Option<BlendMode> {
+        match n {
+            0 => Some(BlendMode::Normal),
+            1 => Some(BlendMode::Alpha25),
+            2 => Some(BlendMode::Alpha50),
+            3 => Some(BlendMode::Additive),
+            4 => Some(BlendMode::Multiply),
+            _ => None,
+        }
+    }
+
+    /// Gets the value of `n` in 080N that selects the blend mode
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Combines the ARGB color `source` drawn over the ARGB color `destination`
+    /// The result is always opaque
+    pub fn blend(self, source: u32, destination: u32) -> u32 {
+        let channel = |color: u32, shift: u32| (color >> shift) & 0xFF;
+        let combine = |source: u32, destination: u32| match self {
+            BlendMode::Normal => source,
+            BlendMode::Alpha25 => (source + destination * 3) / 4,
+            BlendMode::Alpha50 => (source + destination) / 2,
+            BlendMode::Additive => (source + destination).min(0xFF),
+            BlendMode::Multiply => source * destination / 0xFF,
+        };
+
+        [16, 8, 0].into_iter().fold(0xFF00_0000, |color, shift| {
+            color | combine(channel(source, shift), channel(destination, shift)) << shift
+        })
+    }
+}
+
+/// A digitized sound started by 060N
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigitizedSound {
+    /// The number of samples played per second
+    pub sample_rate: u16,
+    /// Unsigned 8 bit samples, centered on `0x80`
+    pub samples: Vec<u8>,
+    /// Whether the sound restarts when it reaches the end
+    pub looping: bool,
+}
+
+/// Moves the pixels of a MEGA-CHIP screen `width` pixels wide right by `dx` and down by `dy` pixels
+/// Pixels moved past the edges are lost and the uncovered pixels are set to the default value
+pub(crate) fn scroll<T: Copy + Default>(pixels: &mut [T], width: usize, dx: isize, dy: isize) {
+    let size = pixels.len();
+    let distance = dy.unsigned_abs().saturating_mul(width).min(size);
+    if dy > 0 {
+        pixels.copy_within(..size - distance, distance);
+        pixels[..distance].fill(T::default());
+    } else {
+        pixels.copy_within(distance.., 0);
+        pixels[size - distance..].fill(T::default());
+    }
+
+    let distance = dx.unsigned_abs().min(width);
+    for row in pixels.chunks_exact_mut(width) {
+        if dx > 0 {
+            row.copy_within(..width - distance, distance);
+            row[..distance].fill(T::default());
+        } else {
+            row.copy_within(distance.., 0);
+            row[width - distance..].fill(T::default());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(BlendMode::from_code(0), Some(BlendMode::Normal));
+        assert_eq!(BlendMode::from_code(4), Some(BlendMode::Multiply));
+        assert_eq!(BlendMode::from_code(5), None);
+        assert_eq!(BlendMode::from_code(BlendMode::Alpha50.code()), Some(BlendMode::Alpha50));
+    }
+
+    #[test]
+    fn test_blend() {
+        let source = 0x80_40_80_FF;
+        let destination = 0xFF_C0_00_80;
+
+        assert_eq!(BlendMode::Normal.blend(source, destination), 0xFF_40_80_FF);
+        assert_eq!(BlendMode::Alpha25.blend(source, destination), 0xFF_A0_20_9F);
+        assert_eq!(BlendMode::Alpha50.blend(source, destination), 0xFF_80_40_BF);
+        assert_eq!(BlendMode::Additive.blend(source, destination), 0xFF_FF_80_FF);
+        assert_eq!(BlendMode::Multiply.blend(source, destination), 0xFF_30_00_80);
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut pixels = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        scroll(&mut pixels, 3, 1, 0);
+        assert_eq!(pixels, [0, 1, 2, 0, 4, 5, 0, 7, 8]);
+        scroll(&mut pixels, 3, 0, -1);
+        assert_eq!(pixels, [0, 4, 5, 0, 7, 8, 0, 0, 0]);
+        scroll(&mut pixels, 3, -2, 1);
+        assert_eq!(pixels, [0, 0, 0, 5, 0, 0, 8, 0, 0]);
+        scroll(&mut pixels, 3, 0, 4);
+        assert_eq!(pixels, [0; 9]);
+    }
+}
@@ -0,0 +1,111 @@
+/// Settings for instructions whose behavior differs between interpreters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// 8XY6 and 8XYE shift `registers[y]` into `registers[x]` instead of shifting `registers[x]` in place
+    pub shift_uses_vy: bool,
+    /// 8XY1, 8XY2 and 8XY3 reset `registers[0xF]` to `0x00`
+    pub logic_resets_vf: bool,
+    /// FX55 and FX65 advance `index_register` past the last register stored or loaded
+    pub load_store_increments_index: bool,
+    /// BNNN jumps to `nnn + registers[x]`, where `x` is the highest nibble of `nnn`, instead of `nnn + registers[0x0]`
+    pub jump_uses_vx: bool,
+    /// Sprites drawn past the edge of the screen wrap around to the opposite edge instead of being clipped
+    pub sprites_wrap: bool,
+    /// DXYN ends the current frame, so at most one sprite is drawn per frame
+    pub display_wait: bool,
+    /// DXY0 draws an 8x16 sprite instead of a 16x16 one in the low resolution mode of SUPER-CHIP
+    pub lores_dxy0_8x16: bool,
+}
+
+impl Quirks {
+    /// The behavior of the original COSMAC VIP interpreter
+    pub const COSMAC_VIP: Quirks = Quirks {
+        shift_uses_vy: true,
+        logic_resets_vf: true,
+        load_store_increments_index: true,
+        jump_uses_vx: false,
+        sprites_wrap: false,
+        display_wait: true,
+        lores_dxy0_8x16: false,
+    };
+
+    /// The behavior of CHIP-48 on the HP-48 calculators
+    pub const CHIP_48: Quirks = Quirks {
+        shift_uses_vy: false,
+        logic_resets_vf: false,
+        load_store_increments_index: false,
+        jump_uses_vx: true,
+        sprites_wrap: false,
+        display_wait: false,
+        lores_dxy0_8x16: false,
+    };
+
+    /// The behavior of SUPER-CHIP 1.1
+    pub const SUPER_CHIP_1_1: Quirks = Quirks {
+        shift_uses_vy: false,
+        logic_resets_vf: false,
+        load_store_increments_index: false,
+        jump_uses_vx: true,
+        sprites_wrap: false,
+        display_wait: false,
+        lores_dxy0_8x16: true,
+    };
+
+    /// The behavior of XO-CHIP as implemented by Octo
+    pub const XO_CHIP: Quirks = Quirks {
+        shift_uses_vy: true,
+        logic_resets_vf: false,
+        load_store_increments_index: true,
+        jump_uses_vx: false,
+        sprites_wrap: true,
+        display_wait: false,
+        lores_dxy0_8x16: false,
+    };
+
+    /// The behavior most commonly assumed by modern interpreters and tutorials
+    pub const MODERN: Quirks = Quirks {
+        shift_uses_vy: false,
+        logic_resets_vf: false,
+        load_store_increments_index: false,
+        jump_uses_vx: false,
+        sprites_wrap: false,
+        display_wait: false,
+        lores_dxy0_8x16: false,
+    };
+
+    /// The names accepted by `Quirks::preset`
+    pub const PRESET_NAMES: [&str; 5] = ["vip", "chip48", "schip", "xochip", "modern"];
+
+    /// Looks up a preset by its short name
+    pub fn preset(name: &str) -> Option<Quirks> {
+        match name {
+            "vip" => Some(Quirks::COSMAC_VIP),
+            "chip48" => Some(Quirks::CHIP_48),
+            "schip" => Some(Quirks::SUPER_CHIP_1_1),
+            "xochip" => Some(Quirks::XO_CHIP),
+            "modern" => Some(Quirks::MODERN),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::COSMAC_VIP
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset() {
+        for name in Quirks::PRESET_NAMES {
+            assert!(Quirks::preset(name).is_some());
+        }
+        assert_eq!(Quirks::preset("schip"), Some(Quirks::SUPER_CHIP_1_1));
+        assert_eq!(Quirks::preset("vip"), Some(Quirks::default()));
+        assert_eq!(Quirks::preset("superchip"), None);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::font::FontSet;
+
+/// A source of the random numbers used by CXNN
+/// The whole state of the source must fit in a `u64` so that save states can capture it
+pub trait RandomSource {
+    /// Gets the next random byte
+    fn next_byte(&mut self) -> u8;
+
+    /// Gets the state that `set_state` restores
+    fn state(&self) -> u64;
+
+    /// Restores a state returned by `state`
+    fn set_state(&mut self, state: u64);
+}
+
+/// The pseudo-random algorithms that `RandomAlgorithm::source` can create
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RandomAlgorithm {
+    /// A fast generator with good statistical quality
+    #[default]
+    Xorshift,
+    /// A short-period generator whose sequences are visibly patterned, like those of early interpreters
+    Patterned,
+}
+
+impl RandomAlgorithm {
+    /// The names accepted by `RandomAlgorithm::from_name`
+    pub const NAMES: [&str; 2] = ["xorshift", "patterned"];
+
+    /// Looks up an algorithm by its short name
+    pub fn from_name(name: &str) -> Option<RandomAlgorithm> {
+        match name {
+            "xorshift" => Some(RandomAlgorithm::Xorshift),
+            "patterned" => Some(RandomAlgorithm::Patterned),
+            _ => None,
+        }
+    }
+
+    /// Gets the short name that `RandomAlgorithm::from_name` accepts
+    pub fn name(&self) -> &'static str {
+        match self {
+            RandomAlgorithm::Xorshift => "xorshift",
+            RandomAlgorithm::Patterned => "patterned",
+        }
+    }
+
+    /// Creates a source that uses the algorithm, starting from `seed`
+    pub fn source(&self, seed: u64) -> Box<dyn RandomSource> {
+        match self {
+            RandomAlgorithm::Xorshift => Box::new(Xorshift::new(seed)),
+            RandomAlgorithm::Patterned => Box::new(PatternedRandom::new(seed)),
+        }
+    }
+}
+
+/// The xorshift64* generator, which takes the top byte of a 64 bit xorshift state scaled by a constant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Xorshift {
+    /// Never zero, since xorshift would only ever produce zero from it
+    state: u64,
+}
+
+impl Xorshift {
+    /// Creates a generator whose state is spread out from `seed` with SplitMix64,
+    /// so that similar seeds give unrelated sequences
+    pub fn new(seed: u64) -> Self {
+        let mut state = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        let mut generator = Xorshift { state: 0 };
+        generator.set_state(state ^ (state >> 31));
+        generator
+    }
+}
+
+impl RandomSource for Xorshift {
+    fn next_byte(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+
+    fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// A state of zero is replaced with one
+    fn set_state(&mut self, state: u64) {
+        self.state = state.max(1);
+    }
+}
+
+/// A 16 bit generator that steps its seed on every call and mixes it with the bytes of the COSMAC VIP font
+/// Its sequence is short and visibly patterned, like the random numbers of early interpreters such as the VIP's,
+/// which some programs were tuned around, but it doesn't reproduce the values of any real interpreter
+/// Reproducing the VIP exactly would need the bytes of the VIP interpreter's code, which this crate doesn't include
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternedRandom {
+    seed: u16,
+}
+
+impl PatternedRandom {
+    /// Creates a generator from the low 16 bits of `seed`
+    pub fn new(seed: u64) -> Self {
+        PatternedRandom { seed: seed as u16 }
+    }
+}
+
+impl RandomSource for PatternedRandom {
+    fn next_byte(&mut self) -> u8 {
+        self.seed = self.seed.wrapping_add(1);
+        let [high, low] = self.seed.to_be_bytes();
+        let code = FontSet::CosmacVip.glyphs();
+        let mixed = high.wrapping_add(code[low as usize % code.len()]);
+        self.seed = u16::from_be_bytes([mixed, low]);
+        mixed ^ low.rotate_right(1)
+    }
+
+    fn state(&self) -> u64 {
+        self.seed as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.seed = state as u16;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Takes the next `count` bytes from `source`
+    fn take(source: &mut dyn RandomSource, count: usize) -> Vec<u8> {
+        (0..count).map(|_| source.next_byte()).collect()
+    }
+
+    #[test]
+    fn test_from_name() {
+        for name in RandomAlgorithm::NAMES {
+            assert_eq!(RandomAlgorithm::from_name(name).map(|algorithm| algorithm.name()), Some(name));
+        }
+        assert_eq!(RandomAlgorithm::from_name("patterned"), Some(RandomAlgorithm::Patterned));
+        assert_eq!(RandomAlgorithm::from_name("os"), None);
+    }
+
+    #[test]
+    fn test_xorshift() {
+        let mut first = Xorshift::new(42);
+        let mut second = Xorshift::new(42);
+        assert_eq!(take(&mut first, 64), take(&mut second, 64));
+        assert_ne!(take(&mut Xorshift::new(43), 64), take(&mut Xorshift::new(42), 64));
+
+        let bytes = take(&mut Xorshift::new(0), 4096);
+        assert!((0..=u8::MAX).all(|value| bytes.contains(&value)));
+
+        let mut zero = Xorshift::new(0);
+        zero.set_state(0);
+        assert_eq!(zero.state(), 1);
+    }
+
+    #[test]
+    fn test_patterned_random() {
+        let mut first = PatternedRandom::new(0x1234);
+        let mut second = PatternedRandom::new(0x1234);
+        assert_eq!(take(&mut first, 64), take(&mut second, 64));
+        assert_ne!(take(&mut PatternedRandom::new(0), 64), take(&mut PatternedRandom::new(1), 64));
+    }
+
+    #[test]
+    fn test_set_state() {
+        for algorithm in [RandomAlgorithm::Xorshift, RandomAlgorithm::Patterned] {
+            let mut source = algorithm.source(7);
+            take(source.as_mut(), 10);
+            let state = source.state();
+            let expected = take(source.as_mut(), 16);
+
+            let mut restored = algorithm.source(99);
+            restored.set_state(state);
+            assert_eq!(take(restored.as_mut(), 16), expected);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::VecDeque;
+
+/// A ring buffer of recent save states for stepping backward in time
+/// Only the newest state is kept whole, and each older state is kept as the compressed difference from the state after it
+pub struct Rewind {
+    /// The most recently recorded state, or the state stepped back to
+    latest: Vec<u8>,
+    /// The differences that turn each state into the one before it, with the newest at the back
+    deltas: VecDeque<Vec<u8>>,
+    /// The most differences kept before the oldest is dropped
+    capacity: usize,
+    /// The most bytes of differences kept before the oldest is dropped
+    byte_limit: usize,
+    /// The total size of `deltas`
+    delta_bytes: usize,
+}
+
+impl Rewind {
+    /// Creates an empty buffer that can step back up to `capacity` states
+    pub fn new(capacity: usize) -> Self {
+        Self::with_byte_limit(capacity, usize::MAX)
+    }
+
+    /// Creates an empty buffer like `new` that also drops the oldest states
+    /// once the differences between them take up more than `byte_limit` bytes
+    pub fn with_byte_limit(capacity: usize, byte_limit: usize) -> Self {
+        Rewind {
+            latest: Vec::new(),
+            deltas: VecDeque::new(),
+            capacity,
+            byte_limit,
+            delta_bytes: 0,
+        }
+    }
+
+    /// Gets the number of states that can be stepped back
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    /// Returns whether there are no states to step back to
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Records `state` as the newest state, dropping the oldest if the buffer is full
+    pub fn record(&mut self, state: Vec<u8>) {
+        // The first state has nothing to step back to
+        if !self.latest.is_empty() && self.capacity > 0 {
+            let delta = diff(&state, &self.latest);
+            self.delta_bytes += delta.len();
+            self.deltas.push_back(delta);
+            while self.deltas.len() > self.capacity || self.delta_bytes > self.byte_limit {
+                let Some(oldest) = self.deltas.pop_front() else {
+                    break;
+                };
+                self.delta_bytes -= oldest.len();
+            }
+        }
+        self.latest = state;
+    }
+
+    /// Steps back to the state recorded before the newest one and returns it, forgetting the newest one
+    /// Returns `None` once the oldest state has been reached
+    pub fn step_back(&mut self) -> Option<&[u8]> {
+        let delta = self.deltas.pop_back()?;
+        self.delta_bytes -= delta.len();
+        apply(&delta, &mut self.latest);
+        Some(&self.latest)
+    }
+}
+
+/// Compresses the bytewise XOR of two states, with the shorter one padded with zeros
+/// The result is the lengths of both states as varints, followed by a list of runs,
+/// each the number of unchanged bytes as a varint, followed by the number of changed bytes as a varint
+/// and the XOR of those bytes
+fn diff(state: &[u8], previous: &[u8]) -> Vec<u8> {
+    let byte = |bytes: &[u8], index: usize| bytes.get(index).copied().unwrap_or(0);
+    let length = state.len().max(previous.len());
+    let mut delta = Vec::new();
+    write_varint(&mut delta, state.len());
+    write_varint(&mut delta, previous.len());
+    let mut position = 0;
+    while position < length {
+        let unchanged = (position..length).take_while(|&index| byte(state, index) == byte(previous, index)).count();
+        position += unchanged;
+        let changed = (position..length).take_while(|&index| byte(state, index) != byte(previous, index)).count();
+
+        write_varint(&mut delta, unchanged);
+        write_varint(&mut delta, changed);
+        delta.extend((position..position + changed).map(|index| byte(state, index) ^ byte(previous, index)));
+        position += changed;
+    }
+    delta
+}
+
+/// Applies a difference from `diff` to `state`, which turns either of the states it was made from into the other
+fn apply(delta: &[u8], state: &mut Vec<u8>) {
+    let mut delta = delta.iter().copied();
+    let first_length = read_varint(&mut delta).unwrap_or(0);
+    let second_length = read_varint(&mut delta).unwrap_or(0);
+    let target_length = if state.len() == first_length { second_length } else { first_length };
+    state.resize(first_length.max(second_length), 0);
+
+    let mut position = 0;
+    while let Some(unchanged) = read_varint(&mut delta) {
+        position += unchanged;
+        let changed = read_varint(&mut delta).unwrap_or(0);
+        for (byte, difference) in state[position..position + changed].iter_mut().zip(&mut delta) {
+            *byte ^= difference;
+        }
+        position += changed;
+    }
+    state.truncate(target_length);
+}
+
+/// Writes `value` seven bits at a time from the lowest, with the top bit set on every byte but the last
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads a value written by `write_varint`, or `None` at the end of `bytes`
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Option<usize> {
+    let mut value = 0;
+    let mut shift = 0;
+    for byte in bytes {
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let previous = vec![0x00; 300];
+        let mut state = previous.clone();
+        state[0] = 0x01;
+        state[200..202].copy_from_slice(&[0xFF, 0x0F]);
+
+        let delta = diff(&state, &previous);
+        assert_eq!(delta, [0xAC, 0x02, 0xAC, 0x02, 0x00, 0x01, 0x01, 0xC7, 0x01, 0x02, 0xFF, 0x0F, 0x62, 0x00]);
+
+        let mut restored = state.clone();
+        apply(&delta, &mut restored);
+        assert_eq!(restored, previous);
+        apply(&delta, &mut restored);
+        assert_eq!(restored, state);
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut rewind = Rewind::new(3);
+        assert!(rewind.step_back().is_none());
+
+        for frame in 0..5 {
+            rewind.record(vec![frame, 0xAA, frame * 2]);
+        }
+        assert_eq!(rewind.len(), 3);
+        assert_eq!(rewind.step_back(), Some(&[3, 0xAA, 6][..]));
+        assert_eq!(rewind.step_back(), Some(&[2, 0xAA, 4][..]));
+
+        rewind.record(vec![7, 0xAA, 7]);
+        assert_eq!(rewind.step_back(), Some(&[2, 0xAA, 4][..]));
+        assert_eq!(rewind.step_back(), Some(&[1, 0xAA, 2][..]));
+        assert!(rewind.step_back().is_none());
+        assert!(rewind.is_empty());
+    }
+
+    #[test]
+    fn test_diff_different_size() {
+        let (longer, shorter) = (vec![0x01, 0x02, 0x03], vec![0x01, 0x05]);
+        let delta = diff(&longer, &shorter);
+
+        let mut restored = longer.clone();
+        apply(&delta, &mut restored);
+        assert_eq!(restored, shorter);
+        apply(&delta, &mut restored);
+        assert_eq!(restored, longer);
+    }
+
+    #[test]
+    fn test_byte_limit() {
+        let mut rewind = Rewind::with_byte_limit(10, 40);
+        for frame in 0..5 {
+            rewind.record(vec![frame; 8]);
+        }
+        // Each difference is 12 bytes, the two lengths and a run of no unchanged and 8 changed bytes
+        assert_eq!(rewind.len(), 3);
+        assert_eq!(rewind.step_back(), Some(&[3; 8][..]));
+
+        let mut rewind = Rewind::with_byte_limit(10, 0);
+        rewind.record(vec![0x01]);
+        rewind.record(vec![0x02]);
+        assert!(rewind.is_empty());
+    }
+
+    #[test]
+    fn test_record_different_size() {
+        let mut rewind = Rewind::new(3);
+        rewind.record(vec![0x01]);
+        rewind.record(vec![0x02]);
+        rewind.record(vec![0x03, 0x04]);
+        assert_eq!(rewind.len(), 2);
+        assert_eq!(rewind.step_back(), Some(&[0x02][..]));
+        assert_eq!(rewind.step_back(), Some(&[0x01][..]));
+    }
+}
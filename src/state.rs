@@ -0,0 +1,324 @@
+use crate::error::StateError;
+
+/// The bytes every save state starts with
+const MAGIC: [u8; 4] = *b"C8ST";
+/// The version of the save state format written by `StateWriter`
+/// States written by other versions are rejected
+pub const STATE_VERSION: u16 = 7;
+/// The size of the magic bytes and version at the start of a save state
+const HEADER_SIZE: usize = MAGIC.len() + 2;
+/// The size of the CRC-32 of the header and contents at the end of a save state
+const CHECKSUM_SIZE: usize = 4;
+/// The size of the pieces `StateWriter::write_sparse_bytes` leaves out when they are all zero
+const SPARSE_CHUNK_SIZE: usize = 256;
+
+/// The lookup table for `crc32`, with the remainder of each byte value
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut remainder = byte as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            remainder = if remainder & 1 != 0 { (remainder >> 1) ^ 0xEDB8_8320 } else { remainder >> 1 };
+            bit += 1;
+        }
+        table[byte] = remainder;
+        byte += 1;
+    }
+    table
+};
+
+/// Computes the CRC-32 of `data`, as used by zlib and PNG
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(u32::MAX, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Builds a save state from big-endian values, with the header in front and the checksum at the end
+pub struct StateWriter {
+    bytes: Vec<u8>,
+}
+
+impl StateWriter {
+    /// Starts a save state with the header for `STATE_VERSION`
+    pub fn new() -> Self {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&STATE_VERSION.to_be_bytes());
+        StateWriter { bytes }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_u128(&mut self, value: u128) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+
+    /// Writes a byte that is 0 for `None`, followed by the value for `Some`
+    pub fn write_option<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        self.write_bool(value.is_some());
+        if let Some(value) = value {
+            write(self, value);
+        }
+    }
+
+    /// Writes `bytes` without their length
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Writes the length of `bytes` followed by `bytes`
+    pub fn write_sized_bytes(&mut self, bytes: &[u8]) {
+        self.write_u32(bytes.len() as u32);
+        self.write_bytes(bytes);
+    }
+
+    /// Writes the length of `bytes`, a bitmap of which chunks of `SPARSE_CHUNK_SIZE` bytes are not all zero
+    /// with the first chunk in the most significant bit, and then only those chunks
+    /// The chunks have fixed positions so that small changes to `bytes` make small changes to the state
+    pub fn write_sparse_bytes(&mut self, bytes: &[u8]) {
+        self.write_u32(bytes.len() as u32);
+        let mut bitmap = vec![0; bytes.len().div_ceil(SPARSE_CHUNK_SIZE).div_ceil(8)];
+        for (index, chunk) in bytes.chunks(SPARSE_CHUNK_SIZE).enumerate() {
+            // Folding the whole chunk rather than stopping at the first nonzero byte lets the loop be vectorized
+            if chunk.iter().fold(0, |bits, &byte| bits | byte) != 0 {
+                bitmap[index / 8] |= 0x80 >> (index % 8);
+            }
+        }
+        self.write_bytes(&bitmap);
+        for (index, chunk) in bytes.chunks(SPARSE_CHUNK_SIZE).enumerate() {
+            if bitmap[index / 8] & (0x80 >> (index % 8)) != 0 {
+                self.write_bytes(chunk);
+            }
+        }
+    }
+
+    /// Appends the checksum and returns the finished save state
+    pub fn finish(mut self) -> Vec<u8> {
+        let checksum = crc32(&self.bytes);
+        self.write_u32(checksum);
+        self.bytes
+    }
+}
+
+/// Reads the big-endian values of a save state written by `StateWriter`
+pub struct StateReader<'a> {
+    contents: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    /// Checks the header and checksum of `data` and starts reading the contents after the header
+    pub fn new(data: &'a [u8]) -> Result<Self, StateError> {
+        if data.len() < HEADER_SIZE + CHECKSUM_SIZE || data[..MAGIC.len()] != MAGIC {
+            return Err(StateError::NotAState);
+        }
+
+        let version = u16::from_be_bytes([data[MAGIC.len()], data[MAGIC.len() + 1]]);
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let (covered, checksum) = data.split_at(data.len() - CHECKSUM_SIZE);
+        if crc32(covered) != u32::from_be_bytes(checksum.try_into().unwrap()) {
+            return Err(StateError::ChecksumMismatch);
+        }
+
+        Ok(StateReader {
+            contents: &covered[HEADER_SIZE..],
+        })
+    }
+
+    /// Reads the next `count` bytes
+    pub fn read_bytes(&mut self, count: usize) -> Result<&'a [u8], StateError> {
+        if count > self.contents.len() {
+            return Err(StateError::Malformed);
+        }
+        let (bytes, rest) = self.contents.split_at(count);
+        self.contents = rest;
+        Ok(bytes)
+    }
+
+    /// Reads the next `N` bytes into an array
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, StateError> {
+        self.read_array().map(u16::from_be_bytes)
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, StateError> {
+        self.read_array().map(u32::from_be_bytes)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, StateError> {
+        self.read_array().map(u64::from_be_bytes)
+    }
+
+    pub fn read_u128(&mut self) -> Result<u128, StateError> {
+        self.read_array().map(u128::from_be_bytes)
+    }
+
+    /// Reads a byte that must be 0 or 1
+    pub fn read_bool(&mut self) -> Result<bool, StateError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Malformed),
+        }
+    }
+
+    /// Reads a value written by `StateWriter::write_option`
+    pub fn read_option<T>(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<T, StateError>,
+    ) -> Result<Option<T>, StateError> {
+        if self.read_bool()? { read(self).map(Some) } else { Ok(None) }
+    }
+
+    /// Reads bytes written by `StateWriter::write_sized_bytes`
+    pub fn read_sized_bytes(&mut self) -> Result<&'a [u8], StateError> {
+        let count = self.read_u32()? as usize;
+        self.read_bytes(count)
+    }
+
+    /// Reads bytes written by `StateWriter::write_sparse_bytes` without copying them anywhere yet
+    pub fn read_sparse_bytes(&mut self) -> Result<SparseBytes<'a>, StateError> {
+        let length = self.read_u32()? as usize;
+        let chunk_count = length.div_ceil(SPARSE_CHUNK_SIZE);
+        let bitmap = self.read_bytes(chunk_count.div_ceil(8))?;
+        let mut chunks = Vec::new();
+        for index in 0..chunk_count {
+            if bitmap[index / 8] & (0x80 >> (index % 8)) != 0 {
+                let offset = index * SPARSE_CHUNK_SIZE;
+                chunks.push((offset, self.read_bytes(SPARSE_CHUNK_SIZE.min(length - offset))?));
+            }
+        }
+        Ok(SparseBytes { length, chunks })
+    }
+
+    /// Checks that every byte of the contents has been read
+    pub fn finish(self) -> Result<(), StateError> {
+        if self.contents.is_empty() { Ok(()) } else { Err(StateError::Malformed) }
+    }
+}
+
+/// Bytes read by `StateReader::read_sparse_bytes`, as the chunks that are not all zero
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseBytes<'a> {
+    length: usize,
+    /// The offset and contents of each chunk that is not all zero
+    chunks: Vec<(usize, &'a [u8])>,
+}
+
+impl SparseBytes<'_> {
+    /// Gets the number of bytes, including the ones left out
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Copies the bytes into `target`, which must be `len` bytes long
+    pub fn copy_to(&self, target: &mut [u8]) {
+        let mut position = 0;
+        for &(offset, chunk) in &self.chunks {
+            target[position..offset].fill(0);
+            target[offset..offset + chunk.len()].copy_from_slice(chunk);
+            position = offset + chunk.len();
+        }
+        target[position..].fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut writer = StateWriter::new();
+        writer.write_u8(0x12);
+        writer.write_u16(0x3456);
+        writer.write_option(Some(0x789A_BCDE), StateWriter::write_u32);
+        writer.write_option(None, StateWriter::write_u128);
+        writer.write_sized_bytes(&[0xF0, 0x0F]);
+        let state = writer.finish();
+
+        let mut reader = StateReader::new(&state).unwrap();
+        assert_eq!(reader.read_u8(), Ok(0x12));
+        assert_eq!(reader.read_u16(), Ok(0x3456));
+        assert_eq!(reader.read_option(StateReader::read_u32), Ok(Some(0x789A_BCDE)));
+        assert_eq!(reader.read_option(StateReader::read_u128), Ok(None));
+        assert_eq!(reader.read_sized_bytes(), Ok(&[0xF0, 0x0F][..]));
+        assert_eq!(reader.read_u8(), Err(StateError::Malformed));
+        reader.finish().unwrap();
+    }
+
+    #[test]
+    fn test_sparse_bytes() {
+        let mut bytes = vec![0; SPARSE_CHUNK_SIZE * 9 + 10];
+        bytes[3] = 0x01;
+        bytes[SPARSE_CHUNK_SIZE * 9 + 9] = 0x02;
+        let mut writer = StateWriter::new();
+        writer.write_sparse_bytes(&bytes);
+        let state = writer.finish();
+        assert_eq!(state.len(), HEADER_SIZE + 4 + 2 + SPARSE_CHUNK_SIZE + 10 + CHECKSUM_SIZE);
+
+        let mut reader = StateReader::new(&state).unwrap();
+        let sparse = reader.read_sparse_bytes().unwrap();
+        reader.finish().unwrap();
+        assert_eq!(sparse.len(), bytes.len());
+        let mut target = vec![0xFF; bytes.len()];
+        sparse.copy_to(&mut target);
+        assert_eq!(target, bytes);
+    }
+
+    #[test]
+    fn test_rejects_bad_states() {
+        let mut writer = StateWriter::new();
+        writer.write_bool(true);
+        let state = writer.finish();
+
+        assert!(matches!(StateReader::new(b"C8ST"), Err(StateError::NotAState)));
+        assert!(matches!(StateReader::new(&state[1..]), Err(StateError::NotAState)));
+
+        let mut corrupt = state.clone();
+        corrupt[HEADER_SIZE] = 0x02;
+        assert!(matches!(StateReader::new(&corrupt), Err(StateError::ChecksumMismatch)));
+
+        let mut future = state.clone();
+        future[MAGIC.len()..HEADER_SIZE].copy_from_slice(&(STATE_VERSION + 1).to_be_bytes());
+        assert_eq!(StateReader::new(&future).err(), Some(StateError::UnsupportedVersion(STATE_VERSION + 1)));
+
+        let reader = StateReader::new(&state).unwrap();
+        assert_eq!(reader.finish(), Err(StateError::Malformed));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::chip8::{
+    CHIP_8X_START_ADDRESS, MEGA_CHIP_MEMORY_SIZE, MEMORY_SIZE, PROGRAM_START_ADDRESS, XO_CHIP_MEMORY_SIZE,
+};
+use crate::quirks::Quirks;
+
+/// A dialect of CHIP-8, which decides the instructions available to a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Variant {
+    /// The original CHIP-8 interpreter for the COSMAC VIP
+    #[default]
+    Chip8,
+    /// The two page hi-res CHIP-8 for the COSMAC VIP, which starts with a 64x64 screen
+    HiresChip8,
+    /// CHIP-8X for the COSMAC VIP with the VP-590 color board, adding a color overlay and a second keypad
+    Chip8X,
+    /// SUPER-CHIP 1.1 for the HP-48 calculators, adding a 128x64 high resolution mode, scrolling and large sprites
+    SuperChip,
+    /// XO-CHIP from the Octo toolchain, extending SUPER-CHIP with 64 KiB of memory, two bitplanes and audio patterns
+    XoChip,
+    /// MEGA-CHIP 8, extending SUPER-CHIP with a 256x192 screen of palette colors, large sprites and digitized sound
+    MegaChip,
+}
+
+impl Variant {
+    /// The names accepted by `Variant::from_name`
+    pub const NAMES: [&str; 6] = ["chip8", "hires", "chip8x", "schip", "xochip", "megachip"];
+
+    /// Looks up a variant by its short name
+    pub fn from_name(name: &str) -> Option<Variant> {
+        match name {
+            "chip8" => Some(Variant::Chip8),
+            "hires" => Some(Variant::HiresChip8),
+            "chip8x" => Some(Variant::Chip8X),
+            "schip" => Some(Variant::SuperChip),
+            "xochip" => Some(Variant::XoChip),
+            "megachip" => Some(Variant::MegaChip),
+            _ => None,
+        }
+    }
+
+    /// Gets the short name of the variant, as accepted by `Variant::from_name`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Chip8 => "chip8",
+            Variant::HiresChip8 => "hires",
+            Variant::Chip8X => "chip8x",
+            Variant::SuperChip => "schip",
+            Variant::XoChip => "xochip",
+            Variant::MegaChip => "megachip",
+        }
+    }
+
+    /// Gets the quirks of the reference interpreter for the variant
+    pub fn default_quirks(&self) -> Quirks {
+        match self {
+            Variant::Chip8 | Variant::HiresChip8 | Variant::Chip8X => Quirks::COSMAC_VIP,
+            Variant::SuperChip | Variant::MegaChip => Quirks::SUPER_CHIP_1_1,
+            Variant::XoChip => Quirks::XO_CHIP,
+        }
+    }
+
+    /// Gets the address programs are loaded at and started from
+    pub fn start_address(&self) -> usize {
+        match self {
+            Variant::Chip8X => CHIP_8X_START_ADDRESS,
+            _ => PROGRAM_START_ADDRESS,
+        }
+    }
+
+    /// Gets the number of nested subroutine calls allowed
+    pub fn stack_depth(&self) -> usize {
+        match self {
+            Variant::Chip8 | Variant::HiresChip8 | Variant::Chip8X => 12,
+            Variant::SuperChip | Variant::XoChip | Variant::MegaChip => 16,
+        }
+    }
+
+    /// Gets the size of memory in bytes
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Variant::Chip8 | Variant::HiresChip8 | Variant::Chip8X | Variant::SuperChip => MEMORY_SIZE,
+            Variant::XoChip => XO_CHIP_MEMORY_SIZE,
+            Variant::MegaChip => MEGA_CHIP_MEMORY_SIZE,
+        }
+    }
+
+    /// Gets the number of RPL user flags saved and loaded by FX75 and FX85
+    pub fn flag_count(&self) -> usize {
+        match self {
+            Variant::Chip8 | Variant::HiresChip8 | Variant::Chip8X | Variant::SuperChip | Variant::MegaChip => 8,
+            Variant::XoChip => 16,
+        }
+    }
+
+    /// Returns whether a program starting with `1260` jumps over the patched interpreter of hi-res CHIP-8
+    /// Under `Chip8` the same opcode is an ordinary jump to 0x260
+    pub fn has_two_page_mode(&self) -> bool {
+        matches!(self, Variant::HiresChip8)
+    }
+
+    /// Returns whether the CHIP-8X instructions are available
+    pub fn has_chip8x_instructions(&self) -> bool {
+        matches!(self, Variant::Chip8X)
+    }
+
+    /// Returns whether the SUPER-CHIP instructions are available
+    pub fn has_superchip_instructions(&self) -> bool {
+        matches!(self, Variant::SuperChip | Variant::XoChip | Variant::MegaChip)
+    }
+
+    /// Returns whether the MEGA-CHIP instructions are available
+    pub fn has_megachip_instructions(&self) -> bool {
+        matches!(self, Variant::MegaChip)
+    }
+
+    /// Returns whether the XO-CHIP instructions are available
+    pub fn has_xochip_instructions(&self) -> bool {
+        matches!(self, Variant::XoChip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        for name in Variant::NAMES {
+            assert_eq!(Variant::from_name(name).map(|variant| variant.name()), Some(name));
+        }
+        assert_eq!(Variant::from_name("hires"), Some(Variant::HiresChip8));
+        assert_eq!(Variant::from_name("chip8x"), Some(Variant::Chip8X));
+        assert_eq!(Variant::from_name("schip"), Some(Variant::SuperChip));
+        assert_eq!(Variant::from_name("xochip"), Some(Variant::XoChip));
+        assert_eq!(Variant::from_name("megachip"), Some(Variant::MegaChip));
+        assert_eq!(Variant::from_name("superchip"), None);
+    }
+}
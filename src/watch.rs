@@ -0,0 +1,75 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// A way an instruction accesses memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// A range of memory whose accesses by instructions are recorded as `WatchHit`s
+/// Fetching instructions, including the second word of F000 NNNN and 01NN NNNN, is not an access
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    /// The first and last address watched
+    pub addresses: RangeInclusive<usize>,
+    /// Whether reads are recorded
+    pub reads: bool,
+    /// Whether writes are recorded
+    pub writes: bool,
+    /// Whether an access ends the cycle with `CycleOutcome::Watchpoint` once the instruction completes,
+    /// rather than only being recorded
+    pub stops: bool,
+}
+
+impl Watchpoint {
+    /// Returns whether the watchpoint records `access`
+    pub fn watches(&self, access: Access) -> bool {
+        match access {
+            Access::Read => self.reads,
+            Access::Write => self.writes,
+        }
+    }
+}
+
+/// An access to a byte of memory caught by a watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    /// The address of the instruction that made the access
+    pub pc: usize,
+    pub address: usize,
+    pub access: Access,
+    /// The byte read, or the byte written
+    pub value: u8,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (verb, preposition) = match self.access {
+            Access::Read => ("read", "from"),
+            Access::Write => ("wrote", "to"),
+        };
+        write!(f, "{:#06X} {verb} {:#04X} {preposition} {:#06X}", self.pc, self.value, self.address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watches() {
+        let watchpoint = Watchpoint { addresses: 0x300..=0x302, reads: false, writes: true, stops: true };
+        assert!(watchpoint.watches(Access::Write));
+        assert!(!watchpoint.watches(Access::Read));
+    }
+
+    #[test]
+    fn test_display() {
+        let hit = WatchHit { pc: 0x204, address: 0x300, access: Access::Write, value: 0x05 };
+        assert_eq!(hit.to_string(), "0x0204 wrote 0x05 to 0x0300");
+        let hit = WatchHit { access: Access::Read, ..hit };
+        assert_eq!(hit.to_string(), "0x0204 read 0x05 from 0x0300");
+    }
+}
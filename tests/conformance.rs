@@ -0,0 +1,454 @@
+//! Runs whole programs through `Chip8` and compares their final screens with the golden images in `tests/golden`
+//!
+//! The programs assembled here check themselves, so a decoding or sequencing bug shows up as a wrong mark or digit.
+//! The tests of the published test ROMs listed in `tests/roms/README.md` are ignored by default, since the ROMs
+//! are not checked in; copy them there and run `cargo test --test conformance -- --ignored` to include them.
+//!
+//! Set `CHIP8_BLESS=1` to write the current screens of the assembled programs as the golden images
+//! instead of comparing them.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chip8::headless::{HeadlessRunner, KeyEvent, Limit, ScreenFormat};
+use chip8::{Chip8, Config, PROGRAM_START_ADDRESS, Quirks, Variant};
+
+/// The most frames a program runs for if it does not halt first
+const FRAME_LIMIT: u64 = 1000;
+/// The instructions emulated per frame by the assembled programs,
+/// which is enough for them to finish within a frame unless they wait for one
+const INSTRUCTIONS_PER_FRAME: usize = 1000;
+/// The instructions emulated per frame by the published test ROMs, close to the speed they were written for
+const ROM_INSTRUCTIONS_PER_FRAME: usize = 15;
+/// The address of the ring drawn by `ChecksProgram` for a check that passed
+const PASS_MARK: u16 = 0x202;
+/// The address of the cross drawn by `ChecksProgram` for a check that failed
+const FAIL_MARK: u16 = 0x205;
+/// The number of marks in a row of the grid drawn by `ChecksProgram`
+const MARKS_PER_ROW: usize = 16;
+/// The address Timendus' test suite reads the platform to test from, which skips its menu
+const PLATFORM_ADDRESS: usize = 0x1FF;
+
+/// A program to run and the golden image to compare its final screen with
+struct Case<'a> {
+    /// The name of the golden image in `tests/golden`, without the extension
+    name: &'a str,
+    variant: Variant,
+    quirks: Quirks,
+    instructions_per_frame: usize,
+    keys: Vec<KeyEvent>,
+    /// The value stored at `PLATFORM_ADDRESS` before running, if any
+    platform: Option<u8>,
+    /// Whether `CHIP8_BLESS` may write the golden image, which is false for images that have to come from elsewhere
+    blessable: bool,
+}
+
+impl<'a> Case<'a> {
+    fn new(name: &'a str, variant: Variant, quirks: Quirks) -> Self {
+        Case {
+            name,
+            variant,
+            quirks,
+            instructions_per_frame: INSTRUCTIONS_PER_FRAME,
+            keys: Vec::new(),
+            platform: None,
+            blessable: true,
+        }
+    }
+
+    /// Runs `program` and compares the final screen with the golden image, or replaces the image when blessing
+    fn run(&self, program: &[u8]) {
+        let mut processor = Chip8::with_config(Config {
+            variant: self.variant,
+            quirks: self.quirks,
+            seed: Some(0),
+            ..Config::default()
+        });
+        if let Some(platform) = self.platform {
+            processor.load_at(&[platform], PLATFORM_ADDRESS).unwrap();
+        }
+        processor.load(program).unwrap();
+
+        let limit = Limit::Frames(FRAME_LIMIT);
+        let mut runner = HeadlessRunner::new(self.instructions_per_frame, limit, self.keys.clone());
+        if let Err(error) = runner.run(&mut processor) {
+            panic!("{} faulted: {error}", self.name);
+        }
+
+        let screen = String::from_utf8(ScreenFormat::Ascii.encode(&processor)).unwrap();
+        let golden_path = golden_dir().join(format!("{}.txt", self.name));
+        if self.blessable && env::var_os("CHIP8_BLESS").is_some() {
+            fs::write(&golden_path, &screen).unwrap();
+            return;
+        }
+
+        let Ok(golden) = fs::read_to_string(&golden_path) else {
+            if !self.blessable {
+                panic!("{} has no golden image '{}', see tests/roms/README.md", self.name, golden_path.display())
+            }
+            panic!("{} has no golden image, run with CHIP8_BLESS=1 to create '{}'", self.name, golden_path.display())
+        };
+        if screen != golden {
+            let actual_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.txt", self.name));
+            fs::write(&actual_path, &screen).unwrap();
+            panic!(
+                "the screen of {} differs from '{}', it was written to '{}':\n{screen}",
+                self.name,
+                golden_path.display(),
+                actual_path.display()
+            );
+        }
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+/// Builds a program from instruction words, starting at `PROGRAM_START_ADDRESS`
+struct Assembler {
+    words: Vec<u16>,
+}
+
+impl Assembler {
+    fn new() -> Self {
+        Assembler { words: Vec::new() }
+    }
+
+    /// Gets the address the next word is placed at
+    fn address(&self) -> u16 {
+        (PROGRAM_START_ADDRESS + self.words.len() * 2) as u16
+    }
+
+    fn emit(&mut self, words: &[u16]) {
+        self.words.extend_from_slice(words);
+    }
+
+    /// Ends the program with a jump to itself
+    fn halt(&mut self) {
+        let address = self.address();
+        self.emit(&[0x1000 | address]);
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|word| word.to_be_bytes()).collect()
+    }
+}
+
+/// Builds a program that draws a grid with a mark for each check, a ring if it passed or a cross if it failed
+/// Checks may use V0 to V9, and VB to VF are reserved for drawing the marks
+struct ChecksProgram {
+    assembler: Assembler,
+    count: usize,
+}
+
+impl ChecksProgram {
+    fn new() -> Self {
+        let mut assembler = Assembler::new();
+        // Jump over the ring at `PASS_MARK` and the cross at `FAIL_MARK`
+        assembler.emit(&[0x1208, 0xE0A0, 0xE0A0, 0x40A0]);
+        ChecksProgram { assembler, count: 0 }
+    }
+
+    /// Adds a check that runs the instructions returned by `code` for the address they start at,
+    /// then passes if `registers[x]` is `value` and `registers[0xF]` was `flag` right after `code`
+    fn check(&mut self, code: impl FnOnce(u16) -> Vec<u16>, x: u16, value: u8, flag: Option<u8>) {
+        let code = code(self.assembler.address());
+        self.assembler.emit(&code);
+
+        let column = (self.count % MARKS_PER_ROW * 4) as u16;
+        let row = (self.count / MARKS_PER_ROW * 4) as u16;
+        let draw = self.assembler.address() + 18;
+        let flag_check = match flag {
+            Some(flag) => 0x3E00 | flag as u16,
+            None => 0x5EE0,
+        };
+        self.assembler.emit(&[
+            0x8EF0,
+            0x6C00 | column,
+            0x6B00 | row,
+            0xA000 | FAIL_MARK,
+            0x3000 | x << 8 | value as u16,
+            0x1000 | draw,
+            flag_check,
+            0x1000 | draw,
+            0xA000 | PASS_MARK,
+            0xDCB3,
+        ]);
+        self.count += 1;
+    }
+
+    fn bytes(mut self) -> Vec<u8> {
+        self.assembler.halt();
+        self.assembler.bytes()
+    }
+}
+
+/// Builds a program that draws the value of a register as a digit for each probe, in a row from the left
+struct ProbesProgram {
+    assembler: Assembler,
+    count: u16,
+}
+
+impl ProbesProgram {
+    fn new() -> Self {
+        ProbesProgram { assembler: Assembler::new(), count: 0 }
+    }
+
+    /// Adds a probe that runs the instructions returned by `code` for the address they start at,
+    /// then draws the low nibble of `registers[x]`
+    fn probe(&mut self, code: impl FnOnce(u16) -> Vec<u16>, x: u16) {
+        let code = code(self.assembler.address());
+        self.assembler.emit(&code);
+        self.assembler.emit(&[0x8D00 | x << 4, 0xFD29, 0x6C00 | (self.count * 5), 0x6B00, 0xDCB5]);
+        self.count += 1;
+    }
+
+    fn bytes(mut self) -> Vec<u8> {
+        self.assembler.halt();
+        self.assembler.bytes()
+    }
+}
+
+/// Checks arithmetic, logic, flags, memory, subroutines, skips and jumps with the modern quirks
+#[test]
+fn test_instructions() {
+    let mut program = ChecksProgram::new();
+    // 8XY4 with and without a carry
+    program.check(|_| vec![0x6012, 0x6134, 0x8014], 0x0, 0x46, Some(0));
+    program.check(|_| vec![0x60FF, 0x6102, 0x8014], 0x0, 0x01, Some(1));
+    // 8XY5 and 8XY7 with and without a borrow
+    program.check(|_| vec![0x6005, 0x6103, 0x8015], 0x0, 0x02, Some(1));
+    program.check(|_| vec![0x6003, 0x6105, 0x8015], 0x0, 0xFE, Some(0));
+    program.check(|_| vec![0x6003, 0x6105, 0x8017], 0x0, 0x02, Some(1));
+    program.check(|_| vec![0x6005, 0x6103, 0x8017], 0x0, 0xFE, Some(0));
+    // 8XY6 and 8XYE shift out the lowest and highest bits
+    program.check(|_| vec![0x6081, 0x8006], 0x0, 0x40, Some(1));
+    program.check(|_| vec![0x6081, 0x800E], 0x0, 0x02, Some(1));
+    program.check(|_| vec![0x6041, 0x800E], 0x0, 0x82, Some(0));
+    // The flag is written after the result when VF is the target
+    program.check(|_| vec![0x6F01, 0x6102, 0x8F14], 0xF, 0x00, None);
+    program.check(|_| vec![0x6FFF, 0x6101, 0x8F14], 0xF, 0x01, None);
+    program.check(|_| vec![0x6F05, 0x6106, 0x8F15], 0xF, 0x00, None);
+    // 8XY1, 8XY2 and 8XY3 leave VF alone
+    program.check(|_| vec![0x6F05, 0x600C, 0x610A, 0x8011], 0x0, 0x0E, Some(5));
+    program.check(|_| vec![0x600C, 0x610A, 0x8012], 0x0, 0x08, None);
+    program.check(|_| vec![0x600C, 0x610A, 0x8013], 0x0, 0x06, None);
+    // 7XNN wraps without touching VF, and 8XY0 copies
+    program.check(|_| vec![0x6FAA, 0x60FF, 0x7002], 0x0, 0x01, Some(0xAA));
+    program.check(|_| vec![0x6000, 0x6177, 0x8010], 0x0, 0x77, None);
+    // FX33 stores each decimal digit
+    for (x, digit) in [(0x0, 1), (0x1, 5), (0x2, 6)] {
+        program.check(|_| vec![0xA300, 0x609C, 0xF033, 0xF265], x, digit, None);
+    }
+    // FX55 and FX65 round trip without moving I, and FX1E moves it
+    program.check(|_| vec![0xA300, 0x6011, 0x6122, 0xF155, 0x6100, 0xF165], 0x1, 0x22, None);
+    program.check(|_| vec![0xA300, 0x6011, 0xF055, 0x6000, 0xF065], 0x0, 0x11, None);
+    program.check(|_| vec![0xA300, 0x6033, 0x6144, 0x6255, 0xF255, 0x6002, 0xF01E, 0xF065], 0x0, 0x55, None);
+    // 2NNN and 00EE return to the instruction after the call
+    program.check(|start| vec![0x6000, 0x2000 | (start + 6), 0x1000 | (start + 10), 0x7001, 0x00EE], 0x0, 0x01, None);
+    // 5XY0 and 9XY0 skip on equal and not equal registers
+    program.check(|_| vec![0x6000, 0x6105, 0x6205, 0x5120, 0x7001, 0x9120, 0x7002], 0x0, 0x02, None);
+    // 3XNN and 4XNN skip on equal and not equal values
+    program.check(|_| vec![0x6007, 0x4007, 0x7001, 0x4008, 0x7010, 0x3018, 0x7001], 0x0, 0x18, None);
+    // EX9E and EXA1 skip on a released key
+    program.check(|_| vec![0x6000, 0x6105, 0xE1A1, 0x7001, 0xE19E, 0x7002], 0x0, 0x02, None);
+    // BNNN jumps relative to V0
+    program.check(|start| vec![0x6100, 0x6002, 0xB000 | (start + 6), 0x6111, 0x7103], 0x1, 0x03, None);
+    // FX15 and FX07 round trip within a frame
+    program.check(|_| vec![0x6030, 0xF015, 0xF107], 0x1, 0x30, None);
+    // CXNN is masked by NN
+    program.check(|_| vec![0x6055, 0xC000], 0x0, 0x00, None);
+    // DXYN reports a collision when it erases a pixel
+    program.check(|_| vec![0xA000 | PASS_MARK, 0x603C, 0x611C, 0xD013, 0xD013], 0x0, 0x3C, Some(1));
+    program.check(|_| vec![0xA000 | PASS_MARK, 0x603C, 0x611C, 0xD013, 0xD013, 0xD013], 0x0, 0x3C, Some(0));
+
+    Case::new("instructions", Variant::Chip8, Quirks::MODERN).run(&program.bytes());
+}
+
+/// Draws every character of the font in two rows of eight, using nested loops
+#[test]
+fn test_font() {
+    let mut program = Assembler::new();
+    program.emit(&[
+        0x6000, // V0 = 0, the character
+        0x6200, // V2 = 0, the row
+        0x6100, // V1 = 0, the column
+        0xF029, 0xD125, 0x7001, 0x7105, // draw V0 and move right
+        0x6307, 0x8302, 0x3300, 0x1206, // repeat until V0 is a multiple of 8
+        0x7206, 0x3010, 0x1204, // start the next row until V0 is 16
+    ]);
+    program.halt();
+
+    Case::new("font", Variant::Chip8, Quirks::COSMAC_VIP).run(&program.bytes());
+}
+
+/// Draws a digit for the behavior of each quirk, so each preset has its own golden image
+fn quirks_program() -> Vec<u8> {
+    let mut program = ProbesProgram::new();
+    // 4 if 8XY6 shifts VY, otherwise 2
+    program.probe(|_| vec![0x6004, 0x6108, 0x8016], 0x0);
+    // 0 if 8XY1 resets VF, otherwise 5
+    program.probe(|_| vec![0x6F05, 0x6000, 0x8011], 0xF);
+    // 7 if FX55 advances I, otherwise 0
+    program.probe(|_| vec![0xA301, 0x6007, 0xF055, 0xA300, 0x6000, 0xF055, 0xF065], 0x0);
+    // 3 if BNNN adds VX, otherwise 2
+    program.probe(
+        |start| {
+            vec![0x6000, 0x6204, 0xB000 | (start + 8), 0x6301, 0x6302, 0x1000 | (start + 14), 0x6303]
+        },
+        0x3,
+    );
+    // 1 if a sprite at the right edge wraps into the left edge, otherwise 0
+    program.probe(|_| vec![0x6000, 0xF029, 0x603E, 0x611F, 0xD011, 0x6000, 0xD011], 0xF);
+    // 1 if every DXYN waits for the next frame, otherwise 5
+    program.probe(|_| vec![0x6005, 0xF015, 0x6838, 0x6910, 0xD891, 0xD891, 0xD891, 0xD891, 0xF007], 0x0);
+    program.bytes()
+}
+
+#[test]
+fn test_quirks_cosmac_vip() {
+    Case::new("quirks-vip", Variant::Chip8, Quirks::COSMAC_VIP).run(&quirks_program());
+}
+
+#[test]
+fn test_quirks_super_chip() {
+    Case::new("quirks-schip", Variant::SuperChip, Quirks::SUPER_CHIP_1_1).run(&quirks_program());
+}
+
+#[test]
+fn test_quirks_xo_chip() {
+    Case::new("quirks-xochip", Variant::XoChip, Quirks::XO_CHIP).run(&quirks_program());
+}
+
+#[test]
+fn test_quirks_modern() {
+    Case::new("quirks-modern", Variant::Chip8, Quirks::MODERN).run(&quirks_program());
+}
+
+/// Draws the large digits in high resolution, then scrolls them down and right
+#[test]
+fn test_super_chip_scrolling() {
+    let mut program = Assembler::new();
+    program.emit(&[
+        0x00FF, // switch to 128x64
+        0x6000, 0x6100, 0x6202, // V0 = 0, V1 = 0, V2 = 2
+        0xF030, 0xD12A, 0x7001, 0x710A, // draw the large V0 and move right
+        0x300A, 0x1208, // repeat until V0 is 10
+        0x00C4, 0x00FB, // scroll down 4 and right 4
+    ]);
+    program.halt();
+
+    Case::new("schip-scrolling", Variant::SuperChip, Quirks::SUPER_CHIP_1_1).run(&program.bytes());
+}
+
+/// Draws to each bitplane and to both at once, including a sprite addressed by F000 NNNN
+#[test]
+fn test_xo_chip_planes() {
+    let mut program = Assembler::new();
+    program.emit(&[
+        0x6000, 0x6100, 0x6200, 0xF029, 0xD125, // draw 0 at 0, 0 in the first plane
+        0xF201, 0x6102, 0xD125, // draw 0 at 2, 0 in the second plane
+        0xF301, 0x6001, 0xF029, 0x6114, 0xD125, // draw 1 in the first plane and 2 in the second at 20, 0
+        0xF000, 0x0230, 0x6128, 0xD122, // draw the two rows of each plane at 0x230 at 40, 0
+    ]);
+    program.halt();
+    while program.address() < 0x230 {
+        program.emit(&[0x0000]);
+    }
+    program.emit(&[0xF0F0, 0x3C3C]);
+
+    Case::new("xochip-planes", Variant::XoChip, Quirks::XO_CHIP).run(&program.bytes());
+}
+
+/// A published test ROM, read from `tests/roms` and run at `ROM_INSTRUCTIONS_PER_FRAME`
+/// Its golden image is the screen the suite documents for every test passing, so it is never blessed
+struct RomCase<'a> {
+    file: &'a str,
+    case: Case<'a>,
+}
+
+impl RomCase<'_> {
+    fn run(mut self) {
+        self.case.instructions_per_frame = ROM_INSTRUCTIONS_PER_FRAME;
+        self.case.blessable = false;
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("roms").join(self.file);
+        let Ok(program) = fs::read(&path) else {
+            panic!("{} needs '{}', see tests/roms/README.md", self.case.name, path.display())
+        };
+        self.case.run(&program);
+    }
+}
+
+/// Presses and releases `key` at the start of `frame` and two frames later
+fn tap(key: u8, frame: u64) -> Vec<KeyEvent> {
+    vec![KeyEvent { frame, key, pressed: true }, KeyEvent { frame: frame + 2, key, pressed: false }]
+}
+
+#[test]
+#[ignore = "needs the published test ROMs in tests/roms"]
+fn test_timendus_chip8_logo() {
+    let case = Case::new("timendus-chip8-logo", Variant::Chip8, Quirks::COSMAC_VIP);
+    RomCase { file: "1-chip8-logo.ch8", case }.run();
+}
+
+#[test]
+#[ignore = "needs the published test ROMs in tests/roms"]
+fn test_timendus_ibm_logo() {
+    let case = Case::new("timendus-ibm-logo", Variant::Chip8, Quirks::COSMAC_VIP);
+    RomCase { file: "2-ibm-logo.ch8", case }.run();
+}
+
+#[test]
+#[ignore = "needs the published test ROMs in tests/roms"]
+fn test_timendus_corax_plus() {
+    let case = Case::new("timendus-corax-plus", Variant::Chip8, Quirks::COSMAC_VIP);
+    RomCase { file: "3-corax+.ch8", case }.run();
+}
+
+#[test]
+#[ignore = "needs the published test ROMs in tests/roms"]
+fn test_timendus_flags() {
+    let case = Case::new("timendus-flags", Variant::Chip8, Quirks::COSMAC_VIP);
+    RomCase { file: "4-flags.ch8", case }.run();
+}
+
+#[test]
+#[ignore = "needs the published test ROMs in tests/roms"]
+fn test_timendus_quirks() {
+    for (name, platform, variant, quirks) in [
+        ("timendus-quirks-chip8", 1, Variant::Chip8, Quirks::COSMAC_VIP),
+        ("timendus-quirks-schip", 2, Variant::SuperChip, Quirks::SUPER_CHIP_1_1),
+        ("timendus-quirks-xochip", 3, Variant::XoChip, Quirks::XO_CHIP),
+    ] {
+        let case = Case { platform: Some(platform), ..Case::new(name, variant, quirks) };
+        RomCase { file: "5-quirks.ch8", case }.run();
+    }
+}
+
+#[test]
+#[ignore = "needs the published test ROMs in tests/roms"]
+fn test_timendus_keypad() {
+    // Run the FX0A test, pressing and releasing key 5 once it is waiting
+    let case = Case {
+        platform: Some(3),
+        keys: tap(0x5, 60),
+        ..Case::new("timendus-keypad", Variant::Chip8, Quirks::COSMAC_VIP)
+    };
+    RomCase { file: "6-keypad.ch8", case }.run();
+}
+
+#[test]
+#[ignore = "needs the published test ROMs in tests/roms"]
+fn test_timendus_beep() {
+    let case = Case::new("timendus-beep", Variant::Chip8, Quirks::COSMAC_VIP);
+    RomCase { file: "7-beep.ch8", case }.run();
+}
+
+#[test]
+#[ignore = "needs the published test ROMs in tests/roms"]
+fn test_bestcoder_opcodes() {
+    let case = Case::new("bestcoder-opcodes", Variant::Chip8, Quirks::CHIP_48);
+    RomCase { file: "BC_test.ch8", case }.run();
+}